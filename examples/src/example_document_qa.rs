@@ -19,14 +19,15 @@ use std::time::Instant;
 
 use anyhow::Result;
 use env_logger::Env;
+use llmchain::BatchedEmbedding;
+use llmchain::CachedEmbedding;
 use llmchain::DatabendEmbedding;
 use llmchain::DatabendLLM;
 use llmchain::DatabendVectorStore;
 use llmchain::DirectoryLoader;
-use llmchain::DocumentLoader;
-use llmchain::DocumentPath;
+use llmchain::DiskCache;
 use llmchain::DocumentRetrievalPrompt;
-use llmchain::DocumentSplitter;
+use llmchain::Indexer;
 use llmchain::LocalDisk;
 use llmchain::MarkdownLoader;
 use llmchain::MarkdownSplitter;
@@ -36,7 +37,7 @@ use llmchain::LLM;
 use log::info;
 
 /// EXPORT DATABEND_DSN=<your-databend-dsn>
-/// cargo run --bin example_document_qa <embedding|query>
+/// cargo run --bin example_document_qa <embedding|plan|query>
 #[tokio::main]
 async fn main() -> Result<()> {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
@@ -51,10 +52,11 @@ async fn main() -> Result<()> {
     if !args.is_empty() {
         let arg = args.get(1).unwrap();
         match arg.as_str() {
-            "embedding" => embeddings(&dsn).await?,
+            "embedding" => embeddings(&dsn, false).await?,
+            "plan" => embeddings(&dsn, true).await?,
             "query" => query(&dsn).await?,
             _ => {
-                info!("cargo run --bin example_document_qa [embedding|query]")
+                info!("cargo run --bin example_document_qa [embedding|plan|query]")
             }
         }
     }
@@ -62,7 +64,8 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-async fn embeddings(databend_dsn: &str) -> Result<()> {
+// Only the files changed since the previous run are embedded, a dry run reports them.
+async fn embeddings(databend_dsn: &str, dry_run: bool) -> Result<()> {
     // dir.
     let curdir = std::env::current_dir()?.to_str().unwrap().to_string();
     let testdata_dir = format!("{}/examples/testdata", curdir);
@@ -72,47 +75,38 @@ async fn embeddings(databend_dsn: &str) -> Result<()> {
     {
         let start = Instant::now();
         // Loader.
-        info!("Prepare to load all the documents {}", directory_dir);
         let directory_loader = DirectoryLoader::create(LocalDisk::create()?)
             .with_loader("**/*.md", MarkdownLoader::create(LocalDisk::create()?));
-        let documents = directory_loader
-            .load(DocumentPath::from_string(&directory_dir))
-            .await?;
-        info!(
-            "Load all the documents {} done, cost: {}",
-            directory_dir,
-            start.elapsed().as_secs()
-        );
-
-        // Splitter.
-        info!(
-            "Prepare to split all the documents, count: {}",
-            documents.len()
-        );
-        let start = Instant::now();
-        let documents = MarkdownSplitter::create().split_documents(&documents)?;
-        info!(
-            "Split all to documents, count: {}, cost: {}",
-            documents.len(),
-            start.elapsed().as_secs()
-        );
 
         // embedding.
-        info!(
-            "Prepare to indexing the documents, count: {}",
-            documents.len()
+        let batched_embedding =
+            BatchedEmbedding::create(Arc::new(DatabendEmbedding::create(databend_dsn)))
+                .with_batch_size(32)
+                .with_progress(|done, total| info!("Embedding documents: {}/{}", done, total));
+        let databend_embedding = CachedEmbedding::create(
+            batched_embedding,
+            DiskCache::create(&format!("{}/.embedding_cache", testdata_dir))?,
         );
-        let start = Instant::now();
-        let databend_embedding = Arc::new(DatabendEmbedding::create(databend_dsn));
         let databend = DatabendVectorStore::create(databend_dsn, databend_embedding);
         databend.init().await?;
 
         // indexing.
-        let uuids = databend.add_documents(&documents).await?;
+        info!("Prepare to index the documents {}", directory_dir);
+        let indexer = Indexer::create(
+            Arc::new(directory_loader),
+            Arc::new(databend),
+            &format!("{}/.index_manifest.json", testdata_dir),
+        )
+        .with_splitter(Arc::new(MarkdownSplitter::create()));
+        let report = if dry_run {
+            indexer.plan(&directory_dir).await?
+        } else {
+            indexer.index(&directory_dir).await?
+        };
         info!(
-            "Indexing the documents done, count: {}, cost: {}",
-            uuids.len(),
-            start.elapsed().as_secs()
+            "Indexing the documents done, cost: {}\n{}",
+            start.elapsed().as_secs(),
+            report
         );
 
         Ok(())
@@ -126,17 +120,20 @@ async fn query(databend_dsn: &str) -> Result<()> {
     let databend_embedding = Arc::new(DatabendEmbedding::create(databend_dsn));
     let databend = DatabendVectorStore::create(databend_dsn, databend_embedding);
     databend.init().await?;
-    let similarities = databend.similarity_search(question, 3).await?;
+    let similarities = databend.similarity_search_with_score(question, 3).await?;
     info!(
         "query: {}, similarity documents: {:?}, cost: {}",
         question,
-        similarities.len(),
+        similarities
+            .iter()
+            .map(|(document, similarity)| format!("{}: {:.3}", document.path, similarity))
+            .collect::<Vec<_>>(),
         start.elapsed().as_secs()
     );
 
     let contexts = similarities
         .iter()
-        .map(|x| format!("context:{}\nsource:{}", x.content, x.path))
+        .map(|(x, _)| format!("context:{}\nsource:{}", x.content, x.path))
         .collect::<Vec<_>>()
         .join("");
     let prompt_template = DocumentRetrievalPrompt::create().with_instructions(vec!["Present your answer in markdown format, including code snippets if have, format the code snippets with SQL type if necessary.",
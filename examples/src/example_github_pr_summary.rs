@@ -18,10 +18,13 @@ use llmchain::DatabendLLM;
 use llmchain::DocumentLoader;
 use llmchain::DocumentPath;
 use llmchain::DocumentSplitter;
+use llmchain::GenerateChunk;
+use llmchain::GenerateStream;
 use llmchain::GithubPRDiffSplitter;
 use llmchain::GithubPRLoader;
 use llmchain::GithubPRSummary;
 use llmchain::Summarize;
+use llmchain::UsageTracker;
 use llmchain_examples::kit::handle_repl;
 use llmchain_examples::kit::ReplAsyncCallback;
 use log::info;
@@ -38,9 +41,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn github_pr_summary(pr: String) -> Result<String> {
+async fn github_pr_summary(pr: String) -> Result<GenerateStream> {
     if pr.is_empty() {
-        return Ok("Input Github PR URL which you want to summary".to_string());
+        return Ok(Box::pin(futures::stream::once(async {
+            Ok(GenerateChunk::Delta(
+                "Input Github PR URL which you want to summary\n".to_string(),
+            ))
+        })));
     }
 
     let (owner, repo, pull_id) = parse_github_pr(&pr)?;
@@ -61,18 +68,20 @@ async fn github_pr_summary(pr: String) -> Result<String> {
         .split_documents(&documents)
         .unwrap();
 
-    let databend_llm = DatabendLLM::create(&databend_dsn);
+    let usage_tracker = UsageTracker::create();
+    let databend_llm = DatabendLLM::create(&databend_dsn).with_usage_tracker(usage_tracker.clone());
     let summary = GithubPRSummary::create(databend_llm);
     summary.add_documents(&documents).await?;
     let pr_summary = summary.final_summary().await?;
 
+    let usage = usage_tracker.total();
     let final_summary = format!(
-        "{}\nTokens:{}\n## Summary(By llmchain.rs)\n{}",
-        pr,
-        summary.tokens(),
-        pr_summary
+        "{}\nTokens:{}, Cost:${:.4}\n## Summary(By llmchain.rs)\n{}",
+        pr, usage.total_tokens, usage.cost, pr_summary
     );
-    Ok(final_summary)
+    Ok(Box::pin(futures::stream::once(async {
+        Ok(GenerateChunk::Delta(final_summary))
+    })))
 }
 
 fn parse_github_pr(url: &str) -> Result<(String, String, usize)> {
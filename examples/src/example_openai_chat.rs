@@ -0,0 +1,42 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use env_logger::Env;
+use llmchain::GenerateStream;
+use llmchain::OpenAI;
+use llmchain::LLM;
+use llmchain_examples::kit::handle_repl;
+use llmchain_examples::kit::ReplAsyncCallback;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+    let callback: Box<ReplAsyncCallback> = Box::new(|input| Box::pin(openai_chat(input)));
+    handle_repl("chat> ", callback).await?;
+
+    Ok(())
+}
+
+async fn openai_chat(input: String) -> Result<GenerateStream> {
+    let api_key = std::env::var("OPENAI_API_KEY")
+        .map_err(|_| {
+            "OPENAI_API_KEY is empty, please EXPORT OPENAI_API_KEY=<your-openai-api-key>"
+                .to_string()
+        })
+        .unwrap();
+
+    let llm = OpenAI::create(api_key);
+    llm.generate_stream(&input).await
+}
@@ -54,7 +54,7 @@ async fn main() -> Result<()> {
 
     // query a similarity document.
     let query = "llmchain";
-    let similarities = databend.similarity_search("llmchain", 1).await?;
+    let similarities = databend.similarity_search_with_score("llmchain", 1).await?;
     info!("query:{}, similarity documents:{:?}", query, similarities);
 
     Ok(())
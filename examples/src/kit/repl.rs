@@ -16,18 +16,19 @@ use std::future::Future;
 use std::io;
 use std::io::Write;
 use std::pin::Pin;
-use std::thread;
-use std::time::Duration;
 
 use anyhow::Result;
 use colored::*;
+use futures::StreamExt;
+use llmchain::GenerateChunk;
+use llmchain::GenerateStream;
 use rustyline::config::Builder;
 use rustyline::error::ReadlineError;
 use rustyline::CompletionType;
 use rustyline::DefaultEditor;
 
 pub type ReplAsyncCallback =
-    dyn Fn(String) -> Pin<Box<dyn Future<Output = Result<String>> + Send>> + Send + Sync;
+    dyn Fn(String) -> Pin<Box<dyn Future<Output = Result<GenerateStream>> + Send>> + Send + Sync;
 
 pub async fn handle_repl(
     hint: &str,
@@ -43,14 +44,18 @@ pub async fn handle_repl(
     loop {
         match rl.readline(hint) {
             Ok(line) => {
-                let result = (callback)(line).await?;
-                let stdout = io::stdout();
-                let mut handle = stdout.lock();
-                for word in result.split_terminator('\n') {
-                    let colored_word = word.green();
-                    writeln!(handle, "{} ", colored_word)?; // print word and a space
+                let mut stream = (callback)(line).await?;
+                while let Some(chunk) = stream.next().await {
+                    let stdout = io::stdout();
+                    let mut handle = stdout.lock();
+                    match chunk? {
+                        GenerateChunk::Delta(delta) => write!(handle, "{}", delta.green())?,
+                        GenerateChunk::Usage { total_tokens, .. } => {
+                            writeln!(handle)?;
+                            writeln!(handle, "{}", format!("Tokens:{}", total_tokens).dimmed())?;
+                        }
+                    }
                     handle.flush()?;
-                    thread::sleep(Duration::from_millis(80)); // sleep for 500ms
                 }
             }
             Err(e) => match e {
@@ -0,0 +1,108 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Recall and latency of the HNSW index against the brute force search, on synthetic embeddings:
+// unit vectors scattered around random centers, as the chunks of a corpus gather by topic.
+//
+//   cargo bench -p llmchain --bench hnsw
+//
+// HNSW_POINTS, HNSW_DIMENSION and HNSW_QUERIES change the size of the dataset.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use llmchain::fake_embedding;
+use llmchain::HnswConfig;
+use llmchain::HnswIndex;
+
+const K: usize = 10;
+
+fn env_or(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(default)
+}
+
+fn synthetic(name: &str, cluster: usize, dimension: usize) -> Vec<f32> {
+    let center = fake_embedding(&format!("cluster {}", cluster), dimension);
+    let noise = fake_embedding(name, dimension);
+    let mut vector = center
+        .iter()
+        .zip(noise)
+        .map(|(x, y)| x + y * 0.5)
+        .collect::<Vec<_>>();
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    vector.iter_mut().for_each(|x| *x /= norm);
+    vector
+}
+
+fn main() {
+    let points = env_or("HNSW_POINTS", 50_000);
+    let dimension = env_or("HNSW_DIMENSION", 128);
+    let queries = env_or("HNSW_QUERIES", 200);
+
+    let clusters = (points / 100).max(1);
+    let vectors = (0..points)
+        .map(|i| synthetic(&format!("point {}", i), i % clusters, dimension))
+        .collect::<Vec<_>>();
+    let queries = (0..queries)
+        .map(|i| synthetic(&format!("query {}", i), i % clusters, dimension))
+        .collect::<Vec<_>>();
+
+    let now = Instant::now();
+    let mut index = HnswIndex::create(HnswConfig::default());
+    for vector in vectors {
+        index.insert(vector);
+    }
+    println!(
+        "build: {} points, dimension {}, {:?}",
+        points,
+        dimension,
+        now.elapsed()
+    );
+
+    let now = Instant::now();
+    let expected = queries
+        .iter()
+        .map(|x| index.brute_force_search(x, K))
+        .collect::<Vec<_>>();
+    let brute_force = now.elapsed() / queries.len() as u32;
+    println!("brute force: {:?}/query", brute_force);
+
+    for ef_search in [16, 32, 64, 128, 256] {
+        index.set_ef_search(ef_search);
+        let mut found = 0;
+        let mut elapsed = Duration::ZERO;
+        for (query, expected) in queries.iter().zip(&expected) {
+            let now = Instant::now();
+            let actual = index.search(query, K);
+            elapsed += now.elapsed();
+            found += actual
+                .iter()
+                .filter(|x| expected.iter().any(|y| y.0 == x.0))
+                .count();
+        }
+
+        let latency = elapsed / queries.len() as u32;
+        println!(
+            "hnsw ef_search {:>3}: recall@{} {:.3}, {:?}/query, {:.1}x faster",
+            ef_search,
+            K,
+            found as f64 / (queries.len() * K) as f64,
+            latency,
+            brute_force.as_secs_f64() / latency.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+// A key-value store for the results of the LLM calls, the values are JSON.
+pub trait Cache: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<String>>;
+    fn set(&self, key: &str, value: &str) -> Result<()>;
+}
+
+pub(crate) fn cache_get<T: DeserializeOwned>(cache: &dyn Cache, key: &str) -> Result<Option<T>> {
+    match cache.get(key)? {
+        // An entry which no longer parses is a miss, it's overwritten by the new result.
+        Some(value) => match serde_json::from_str(&value) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) => {
+                warn!("cache entry {} is invalid: {}", key, e);
+                Ok(None)
+            }
+        },
+        None => Ok(None),
+    }
+}
+
+pub(crate) fn cache_set<T: Serialize>(cache: &dyn Cache, key: &str, value: &T) -> Result<()> {
+    cache.set(key, &serde_json::to_string(value)?)
+}
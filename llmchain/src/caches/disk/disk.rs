@@ -0,0 +1,60 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::Cache;
+
+// On-disk cache, one file per key under the directory, it survives the restarts.
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    pub fn create(dir: &str) -> Result<Arc<Self>> {
+        fs::create_dir_all(dir)?;
+        Ok(Arc::new(DiskCache {
+            dir: PathBuf::from(dir),
+        }))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+impl Cache for DiskCache {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        match fs::read_to_string(self.path(key)) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        // Written aside then renamed, a reader never sees a partial entry.
+        let tmp = self
+            .dir
+            .join(format!("{}.{}.tmp", key, uuid::Uuid::new_v4()));
+        fs::write(&tmp, value)?;
+        fs::rename(&tmp, self.path(key))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use anyhow::Result;
+use lru::LruCache;
+use parking_lot::Mutex;
+
+use crate::Cache;
+
+// In-memory cache, the least recently used entries are evicted beyond the capacity.
+pub struct MemoryCache {
+    entries: Mutex<LruCache<String, String>>,
+}
+
+impl MemoryCache {
+    pub fn create(capacity: usize) -> Arc<Self> {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Arc::new(MemoryCache {
+            entries: Mutex::new(LruCache::new(capacity)),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().is_empty()
+    }
+}
+
+impl Cache for MemoryCache {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.entries.lock().get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        self.entries.lock().put(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
@@ -11,8 +11,15 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+mod model_registry;
 mod string;
 mod token;
 
+pub use model_registry::ModelInfo;
+pub use model_registry::ModelRegistry;
 pub use string::escape_sql_string;
+pub use string::quote_sql_string;
+pub use string::validate_sql_identifier;
 pub use token::chat_tokens;
+pub use token::completion_budget;
+pub use token::Tokenizer;
@@ -0,0 +1,166 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use parking_lot::RwLock;
+
+use crate::Tokenizer;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelInfo {
+    pub name: String,
+    // The context window in tokens, shared by the prompt and the completion.
+    pub context_length: usize,
+    // The most tokens the model generates in one answer, the context window if it has no lower cap.
+    pub max_output_tokens: usize,
+    // The dimension of the vectors, for the embedding models.
+    pub embedding_dimension: Option<usize>,
+    pub tokenizer: Tokenizer,
+    // Dollars per 1K tokens.
+    pub prompt_price: f64,
+    pub completion_price: f64,
+}
+
+impl ModelInfo {
+    pub fn create(name: &str, context_length: usize, tokenizer: Tokenizer) -> Self {
+        ModelInfo {
+            name: name.to_string(),
+            context_length,
+            max_output_tokens: context_length,
+            embedding_dimension: None,
+            tokenizer,
+            prompt_price: 0.0,
+            completion_price: 0.0,
+        }
+    }
+
+    pub fn with_max_output_tokens(mut self, max_output_tokens: usize) -> Self {
+        self.max_output_tokens = max_output_tokens;
+        self
+    }
+
+    pub fn with_embedding_dimension(mut self, dimension: usize) -> Self {
+        self.embedding_dimension = Some(dimension);
+        self
+    }
+
+    pub fn with_prices(mut self, prompt_price: f64, completion_price: f64) -> Self {
+        self.prompt_price = prompt_price;
+        self.completion_price = completion_price;
+        self
+    }
+
+    pub fn cost(&self, prompt_tokens: usize, completion_tokens: usize) -> f64 {
+        (prompt_tokens as f64 * self.prompt_price
+            + completion_tokens as f64 * self.completion_price)
+            / 1000.0
+    }
+}
+
+pub struct ModelRegistry {
+    models: RwLock<HashMap<String, ModelInfo>>,
+}
+
+impl ModelRegistry {
+    pub fn create() -> Self {
+        let registry = ModelRegistry {
+            models: RwLock::new(HashMap::new()),
+        };
+
+        let models = vec![
+            // Chat models.
+            ModelInfo::create("gpt-3.5-turbo", 16385, Tokenizer::Cl100kBase)
+                .with_max_output_tokens(4096)
+                .with_prices(0.0015, 0.002),
+            ModelInfo::create("gpt-3.5-turbo-16k", 16385, Tokenizer::Cl100kBase)
+                .with_max_output_tokens(4096)
+                .with_prices(0.003, 0.004),
+            ModelInfo::create("gpt-4", 8192, Tokenizer::Cl100kBase).with_prices(0.03, 0.06),
+            ModelInfo::create("gpt-4-32k", 32768, Tokenizer::Cl100kBase).with_prices(0.06, 0.12),
+            ModelInfo::create("gpt-4-turbo", 128000, Tokenizer::Cl100kBase)
+                .with_max_output_tokens(4096)
+                .with_prices(0.01, 0.03),
+            ModelInfo::create("gpt-4o", 128000, Tokenizer::O200kBase)
+                .with_max_output_tokens(16384)
+                .with_prices(0.005, 0.015),
+            ModelInfo::create("gpt-4o-mini", 128000, Tokenizer::O200kBase)
+                .with_max_output_tokens(16384)
+                .with_prices(0.00015, 0.0006),
+            // Embedding models.
+            ModelInfo::create("text-embedding-ada-002", 8191, Tokenizer::Cl100kBase)
+                .with_embedding_dimension(1536)
+                .with_prices(0.0001, 0.0),
+            ModelInfo::create("text-embedding-3-small", 8191, Tokenizer::Cl100kBase)
+                .with_embedding_dimension(1536)
+                .with_prices(0.00002, 0.0),
+            ModelInfo::create("text-embedding-3-large", 8191, Tokenizer::Cl100kBase)
+                .with_embedding_dimension(3072)
+                .with_prices(0.00013, 0.0),
+            // Anthropic models, their tokenizer is not public, cl100k is a close estimate.
+            ModelInfo::create("claude-3-5-sonnet", 200000, Tokenizer::Cl100kBase)
+                .with_max_output_tokens(8192)
+                .with_prices(0.003, 0.015),
+            ModelInfo::create("claude-3-5-haiku", 200000, Tokenizer::Cl100kBase)
+                .with_max_output_tokens(8192)
+                .with_prices(0.0008, 0.004),
+            ModelInfo::create("claude-3-opus", 200000, Tokenizer::Cl100kBase)
+                .with_max_output_tokens(4096)
+                .with_prices(0.015, 0.075),
+            ModelInfo::create("claude-3-haiku", 200000, Tokenizer::Cl100kBase)
+                .with_max_output_tokens(4096)
+                .with_prices(0.00025, 0.00125),
+        ];
+        for model in models {
+            registry.register(model);
+        }
+
+        registry
+    }
+
+    // The registry used by the providers, register the custom or self-hosted models here.
+    pub fn global() -> &'static ModelRegistry {
+        static REGISTRY: OnceLock<ModelRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(ModelRegistry::create)
+    }
+
+    pub fn register(&self, model: ModelInfo) {
+        self.models.write().insert(model.name.clone(), model);
+    }
+
+    // Find the model by name, a dated snapshot like `gpt-4-0613` falls back to
+    // the longest registered name it starts with.
+    pub fn get(&self, name: &str) -> Option<ModelInfo> {
+        let models = self.models.read();
+        if let Some(model) = models.get(name) {
+            return Some(model.clone());
+        }
+
+        models
+            .values()
+            .filter(|model| name.starts_with(&model.name))
+            .max_by_key(|model| model.name.len())
+            .map(|model| ModelInfo {
+                name: name.to_string(),
+                ..model.clone()
+            })
+    }
+
+    // Unknown models are assumed to be a 4K context window, cl100k tokenizer and free.
+    pub fn get_or_default(&self, name: &str) -> ModelInfo {
+        self.get(name)
+            .unwrap_or_else(|| ModelInfo::create(name, 4096, Tokenizer::default()))
+    }
+}
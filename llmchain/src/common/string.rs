@@ -12,10 +12,43 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use anyhow::bail;
+use anyhow::Result;
+
+// Escapes the content of a single-quoted SQL string, Databend reads back the very same text.
 pub fn escape_sql_string(input: &str) -> String {
-    input
-        .replace('\\', "\\\\")
-        .replace('\'', "''")
-        .replace('\n', " ")
-        .replace('\r', "\\r")
+    let mut output = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '\\' => output.push_str("\\\\"),
+            '\'' => output.push_str("''"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\0' => output.push_str("\\0"),
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+// The SQL literal of the string, every value formatted into a Databend query goes through it.
+pub fn quote_sql_string(input: &str) -> String {
+    format!("'{}'", escape_sql_string(input))
+}
+
+// The database and table names can't be quoted as values, only the plain identifiers are allowed.
+pub fn validate_sql_identifier(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|x| x.is_ascii_alphabetic() || x == '_')
+        && chars.all(|x| x.is_ascii_alphanumeric() || x == '_');
+    if !valid {
+        bail!(
+            "invalid SQL identifier {:?}, only letters, digits and underscores are allowed",
+            name
+        );
+    }
+    Ok(())
 }
@@ -12,10 +12,77 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::Arc;
+
+use anyhow::bail;
 use anyhow::Result;
-use tiktoken_rs::r50k_base;
+use parking_lot::Mutex;
+use tiktoken_rs::cl100k_base_singleton;
+use tiktoken_rs::o200k_base_singleton;
+use tiktoken_rs::r50k_base_singleton;
+use tiktoken_rs::CoreBPE;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tokenizer {
+    // gpt-3.5-turbo, gpt-4 and the embedding models.
+    #[default]
+    Cl100kBase,
+    // gpt-4o.
+    O200kBase,
+    // GPT-3 models like davinci.
+    R50kBase,
+}
 
+impl Tokenizer {
+    fn bpe(&self) -> Arc<Mutex<CoreBPE>> {
+        match self {
+            Tokenizer::Cl100kBase => cl100k_base_singleton(),
+            Tokenizer::O200kBase => o200k_base_singleton(),
+            Tokenizer::R50kBase => r50k_base_singleton(),
+        }
+    }
+
+    pub fn split(&self, input: &str) -> Result<Vec<String>> {
+        self.bpe().lock().split_by_token(input, true)
+    }
+
+    pub fn count(&self, input: &str) -> usize {
+        self.bpe().lock().encode_with_special_tokens(input).len()
+    }
+}
+
+// Split by r50k_base as always, `Tokenizer` splits them for a model.
 pub fn chat_tokens(input: &str) -> Result<Vec<String>> {
-    let rke = r50k_base()?;
-    rke.split_by_token(input, true)
+    Tokenizer::R50kBase.split(input)
+}
+
+// How many tokens the completion may use once the prompt is in the context window,
+// at most the `max_output_tokens` the model generates in one answer.
+// Fails if the prompt leaves less than `reserved_tokens` for the completion.
+pub fn completion_budget(
+    prompt_tokens: usize,
+    context_window: usize,
+    max_output_tokens: usize,
+    reserved_tokens: usize,
+) -> Result<u16> {
+    if prompt_tokens >= context_window {
+        bail!(
+            "prompt has {} tokens, exceeds the context window of {} tokens",
+            prompt_tokens,
+            context_window
+        );
+    }
+
+    let available = context_window - prompt_tokens;
+    if available < reserved_tokens {
+        bail!(
+            "prompt has {} tokens, leaves {} tokens of the context window {} for the completion, less than the reserved {} tokens",
+            prompt_tokens,
+            available,
+            context_window,
+            reserved_tokens
+        );
+    }
+
+    Ok(available.min(max_output_tokens).min(u16::MAX as usize) as u16)
 }
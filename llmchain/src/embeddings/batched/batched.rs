@@ -0,0 +1,168 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use anyhow::bail;
+use anyhow::Result;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use log::info;
+use parking_lot::RwLock;
+
+use crate::Document;
+use crate::Documents;
+use crate::Embedding;
+use crate::Tokenizer;
+
+type ProgressFn = dyn Fn(usize, usize) + Send + Sync;
+
+// Splits the documents into batches embedded concurrently, the embeddings keep the order of the documents.
+pub struct BatchedEmbedding {
+    embedding: Arc<dyn Embedding>,
+
+    // The maximum number of documents in a batch.
+    batch_size: RwLock<usize>,
+
+    // The maximum number of tokens in a batch, a larger document makes a batch on its own.
+    batch_tokens: RwLock<Option<usize>>,
+
+    // The maximum number of batches embedded at the same time.
+    concurrency: RwLock<usize>,
+
+    // Called with the number of the embedded documents and the total, after every batch.
+    progress: RwLock<Option<Arc<ProgressFn>>>,
+}
+
+impl BatchedEmbedding {
+    pub fn create(embedding: Arc<dyn Embedding>) -> Arc<Self> {
+        Arc::new(BatchedEmbedding {
+            embedding,
+            batch_size: RwLock::new(100),
+            batch_tokens: RwLock::new(None),
+            concurrency: RwLock::new(4),
+            progress: RwLock::new(None),
+        })
+    }
+
+    pub fn with_batch_size(self: &Arc<Self>, batch_size: usize) -> Arc<Self> {
+        *self.batch_size.write() = batch_size.max(1);
+        self.clone()
+    }
+
+    pub fn with_batch_tokens(self: &Arc<Self>, batch_tokens: usize) -> Arc<Self> {
+        *self.batch_tokens.write() = Some(batch_tokens);
+        self.clone()
+    }
+
+    pub fn with_concurrency(self: &Arc<Self>, concurrency: usize) -> Arc<Self> {
+        *self.concurrency.write() = concurrency.max(1);
+        self.clone()
+    }
+
+    pub fn with_progress<F>(self: &Arc<Self>, progress: F) -> Arc<Self>
+    where F: Fn(usize, usize) + Send + Sync + 'static {
+        *self.progress.write() = Some(Arc::new(progress));
+        self.clone()
+    }
+
+    fn batches(&self, inputs: &Documents) -> Vec<Documents> {
+        let batch_size = *self.batch_size.read();
+        let batch_tokens = *self.batch_tokens.read();
+        let tokenizer = Tokenizer::default();
+
+        let mut batches = vec![];
+        let mut batch: Vec<Document> = vec![];
+        let mut tokens = 0;
+        for document in inputs.iter() {
+            let document_tokens = match batch_tokens {
+                Some(_) => tokenizer.count(&document.content),
+                None => 0,
+            };
+            let full = batch.len() >= batch_size
+                || batch_tokens.is_some_and(|x| tokens + document_tokens > x);
+            if full && !batch.is_empty() {
+                batches.push(Documents::from(std::mem::take(&mut batch)));
+                tokens = 0;
+            }
+            tokens += document_tokens;
+            batch.push(document);
+        }
+        if !batch.is_empty() {
+            batches.push(Documents::from(batch));
+        }
+        batches
+    }
+}
+
+#[async_trait::async_trait]
+impl Embedding for BatchedEmbedding {
+    fn model(&self) -> String {
+        self.embedding.model()
+    }
+
+    async fn embed_query(&self, input: &str) -> Result<Vec<f32>> {
+        self.embedding.embed_query(input).await
+    }
+
+    async fn embed_documents(&self, inputs: &Documents) -> Result<Vec<Vec<f32>>> {
+        let batches = self.batches(inputs);
+        let total = inputs.len();
+        let total_batches = batches.len();
+        let concurrency = *self.concurrency.read();
+        let progress = self.progress.read().clone();
+        let embedded = AtomicUsize::new(0);
+
+        // Buffered keeps the order of the batches, whatever the order they complete in,
+        // and the first failed batch stops the others.
+        let results = futures::stream::iter(batches.into_iter().enumerate())
+            .map(|(i, batch)| {
+                let progress = progress.clone();
+                let embedded = &embedded;
+                Ok(async move {
+                    let now = std::time::Instant::now();
+                    let embeddings = self.embedding.embed_documents(&batch).await?;
+                    if embeddings.len() != batch.len() {
+                        bail!(
+                            "batch {} got {} embeddings for {} documents",
+                            i + 1,
+                            embeddings.len(),
+                            batch.len()
+                        );
+                    }
+
+                    let done = embedded.fetch_add(batch.len(), Ordering::SeqCst) + batch.len();
+                    info!(
+                        "embedding batch {}/{}, documents {}/{}, time: {:?}",
+                        i + 1,
+                        total_batches,
+                        done,
+                        total,
+                        now.elapsed()
+                    );
+                    if let Some(progress) = progress {
+                        progress(done, total);
+                    }
+                    Ok(embeddings)
+                })
+            })
+            .try_buffered(concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        Ok(results.into_iter().flatten().collect())
+    }
+}
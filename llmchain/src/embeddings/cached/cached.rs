@@ -0,0 +1,115 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use anyhow::bail;
+use anyhow::Result;
+use parking_lot::RwLock;
+
+use crate::cache_get;
+use crate::cache_set;
+use crate::Cache;
+use crate::Documents;
+use crate::Embedding;
+
+// Embeds only the documents whose content is not in the cache yet,
+// keyed on the model of the embedding and the content md5.
+pub struct CachedEmbedding {
+    embedding: Arc<dyn Embedding>,
+    cache: Arc<dyn Cache>,
+
+    // Separates the entries of the embeddings sharing a cache beyond their model.
+    namespace: RwLock<String>,
+}
+
+impl CachedEmbedding {
+    pub fn create(embedding: Arc<dyn Embedding>, cache: Arc<dyn Cache>) -> Arc<Self> {
+        Arc::new(CachedEmbedding {
+            embedding,
+            cache,
+            namespace: RwLock::new("".to_string()),
+        })
+    }
+
+    pub fn with_namespace(self: &Arc<Self>, namespace: &str) -> Arc<Self> {
+        *self.namespace.write() = namespace.to_string();
+        self.clone()
+    }
+
+    fn key(&self, content_md5: &str) -> String {
+        let digest = md5::compute(format!(
+            "{}\n{}\n{}",
+            self.embedding.model(),
+            self.namespace.read(),
+            content_md5
+        ));
+        format!("embedding-{:x}", digest)
+    }
+}
+
+#[async_trait::async_trait]
+impl Embedding for CachedEmbedding {
+    fn model(&self) -> String {
+        self.embedding.model()
+    }
+
+    async fn embed_query(&self, input: &str) -> Result<Vec<f32>> {
+        let key = self.key(&format!("{:x}", md5::compute(input)));
+        if let Some(embedding) = cache_get(self.cache.as_ref(), &key)? {
+            return Ok(embedding);
+        }
+
+        let embedding = self.embedding.embed_query(input).await?;
+        cache_set(self.cache.as_ref(), &key, &embedding)?;
+        Ok(embedding)
+    }
+
+    async fn embed_documents(&self, inputs: &Documents) -> Result<Vec<Vec<f32>>> {
+        let mut keys = Vec::with_capacity(inputs.len());
+        let mut embeddings = Vec::with_capacity(inputs.len());
+        let missing = Documents::create();
+        for document in inputs.iter() {
+            let key = self.key(&document.content_md5);
+            let embedding: Option<Vec<f32>> = cache_get(self.cache.as_ref(), &key)?;
+            if embedding.is_none() {
+                missing.push(document.clone());
+            }
+            keys.push(key);
+            embeddings.push(embedding);
+        }
+
+        if !missing.is_empty() {
+            let missing_embeddings = self.embedding.embed_documents(&missing).await?;
+            // Nothing is cached from a short answer, the vectors would be assigned to the wrong documents.
+            if missing_embeddings.len() != missing.len() {
+                bail!(
+                    "got {} embeddings for {} documents",
+                    missing_embeddings.len(),
+                    missing.len()
+                );
+            }
+
+            let mut missing_embeddings = missing_embeddings.into_iter();
+            for (key, embedding) in keys.iter().zip(embeddings.iter_mut()) {
+                if embedding.is_none() {
+                    *embedding = missing_embeddings.next();
+                    cache_set(self.cache.as_ref(), key, embedding)?;
+                }
+            }
+        }
+
+        Ok(embeddings.into_iter().flatten().collect())
+    }
+}
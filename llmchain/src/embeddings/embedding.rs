@@ -18,6 +18,11 @@ use crate::Documents;
 
 #[async_trait::async_trait]
 pub trait Embedding: Send + Sync {
+    // The model the vectors come from, the vectors of two models never mix in a cache.
+    fn model(&self) -> String {
+        "".to_string()
+    }
+
     async fn embed_query(&self, input: &str) -> Result<Vec<f32>>;
     async fn embed_documents(&self, inputs: &Documents) -> Result<Vec<Vec<f32>>>;
 }
@@ -0,0 +1,70 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+
+use crate::Documents;
+use crate::Embedding;
+
+// Embeds the texts into deterministic pseudo-random unit vectors seeded by their md5,
+// the same text always gets the same vector, without any model.
+pub struct FakeEmbedding {
+    dimension: usize,
+}
+
+impl FakeEmbedding {
+    pub fn create(dimension: usize) -> Self {
+        FakeEmbedding { dimension }
+    }
+}
+
+#[async_trait::async_trait]
+impl Embedding for FakeEmbedding {
+    fn model(&self) -> String {
+        format!("fake-{}", self.dimension)
+    }
+
+    async fn embed_query(&self, input: &str) -> Result<Vec<f32>> {
+        Ok(fake_embedding(input, self.dimension))
+    }
+
+    async fn embed_documents(&self, inputs: &Documents) -> Result<Vec<Vec<f32>>> {
+        Ok(inputs
+            .iter()
+            .map(|x| fake_embedding(&x.content, self.dimension))
+            .collect())
+    }
+}
+
+pub fn fake_embedding(text: &str, dimension: usize) -> Vec<f32> {
+    let digest = md5::compute(text);
+    let mut seed = u64::from_le_bytes(digest.0[..8].try_into().unwrap());
+
+    // splitmix64.
+    let mut vector = Vec::with_capacity(dimension);
+    for _ in 0..dimension {
+        seed = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        vector.push((z >> 11) as f32 / (1u64 << 53) as f32 * 2.0 - 1.0);
+    }
+
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
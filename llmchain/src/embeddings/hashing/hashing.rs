@@ -0,0 +1,171 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use parking_lot::RwLock;
+
+use crate::Documents;
+use crate::Embedding;
+
+// A TF-IDF vectorizer running locally on CPU, without any model file nor network.
+// The words and the pairs of adjacent words are hashed into `dimension` buckets,
+// similar texts share buckets and get close vectors.
+//
+// The IDF weights are optional, fit them on the corpus before indexing it:
+// the documents and the queries must be embedded with the same weights.
+pub struct HashingEmbedding {
+    dimension: usize,
+
+    // Also hash the pairs of adjacent words, they keep a bit of the word order.
+    bigrams: RwLock<bool>,
+
+    // The inverse document frequency of every bucket, fitted on a corpus.
+    idf: RwLock<Option<Vec<f32>>>,
+}
+
+impl HashingEmbedding {
+    pub fn create(dimension: usize) -> Arc<Self> {
+        Arc::new(HashingEmbedding {
+            dimension: dimension.max(1),
+            bigrams: RwLock::new(true),
+            idf: RwLock::new(None),
+        })
+    }
+
+    pub fn with_bigrams(self: &Arc<Self>, bigrams: bool) -> Arc<Self> {
+        *self.bigrams.write() = bigrams;
+        self.clone()
+    }
+
+    // Fits the IDF weights on the documents, the terms found in most of them weigh less.
+    pub fn with_idf(self: &Arc<Self>, documents: &Documents) -> Arc<Self> {
+        let mut df = vec![0usize; self.dimension];
+        for document in documents.iter() {
+            let mut seen = vec![false; self.dimension];
+            for (bucket, _) in self.buckets(&document.content) {
+                if !seen[bucket] {
+                    seen[bucket] = true;
+                    df[bucket] += 1;
+                }
+            }
+        }
+
+        // Smoothed as sklearn does: ln((1 + n) / (1 + df)) + 1.
+        let n = documents.len() as f32;
+        let idf = df
+            .iter()
+            .map(|x| ((1.0 + n) / (1.0 + *x as f32)).ln() + 1.0)
+            .collect();
+        *self.idf.write() = Some(idf);
+        self.clone()
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    pub fn embed(&self, text: &str) -> Vec<f32> {
+        let mut tf = vec![0f32; self.dimension];
+        for (bucket, sign) in self.buckets(text) {
+            tf[bucket] += sign;
+        }
+
+        // Sublinear term frequency, a word repeated many times doesn't take over the vector.
+        let idf = self.idf.read();
+        let mut vector = tf
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let weight = idf.as_ref().map_or(1.0, |idf| idf[i]);
+                x.signum() * (1.0 + x.abs()).ln() * weight
+            })
+            .collect::<Vec<_>>();
+
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        vector
+    }
+
+    // The bucket of every term with its sign, the signs cancel out the hash collisions on average.
+    fn buckets(&self, text: &str) -> Vec<(usize, f32)> {
+        let words = words(text);
+        let mut terms = words
+            .iter()
+            .map(|x| fnv1a(x.as_bytes()))
+            .collect::<Vec<_>>();
+        if *self.bigrams.read() {
+            terms.extend(
+                words
+                    .windows(2)
+                    .map(|x| fnv1a(format!("{} {}", x[0], x[1]).as_bytes())),
+            );
+        }
+
+        terms
+            .into_iter()
+            .map(|hash| {
+                let bucket = (hash % self.dimension as u64) as usize;
+                let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+                (bucket, sign)
+            })
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl Embedding for HashingEmbedding {
+    // Every setting changes the vectors, the fitted weights too.
+    fn model(&self) -> String {
+        let idf = self.idf.read().as_ref().map(|x| {
+            let bytes = x.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>();
+            format!("{:x}", md5::compute(bytes))
+        });
+        format!(
+            "hashing-{}-bigrams-{}-idf-{}",
+            self.dimension,
+            self.bigrams.read(),
+            idf.unwrap_or_default()
+        )
+    }
+
+    async fn embed_query(&self, input: &str) -> Result<Vec<f32>> {
+        Ok(self.embed(input))
+    }
+
+    async fn embed_documents(&self, inputs: &Documents) -> Result<Vec<Vec<f32>>> {
+        Ok(inputs.iter().map(|x| self.embed(&x.content)).collect())
+    }
+}
+
+// The lowercase alphanumeric words of the text.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_lowercase())
+        .collect()
+}
+
+// FNV-1a, stable across the runs and the platforms unlike the std hasher.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
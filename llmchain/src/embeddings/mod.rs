@@ -12,10 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod batched;
+mod cached;
 mod databend;
 mod embedding;
+mod fake;
+mod hashing;
+mod llm;
 mod openai;
 
+pub use batched::BatchedEmbedding;
+pub use cached::CachedEmbedding;
 pub use databend::DatabendEmbedding;
 pub use embedding::Embedding;
+pub use fake::fake_embedding;
+pub use fake::FakeEmbedding;
+pub use hashing::HashingEmbedding;
+pub use llm::LLMEmbedding;
 pub use openai::OpenAIEmbedding;
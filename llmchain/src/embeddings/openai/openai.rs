@@ -35,6 +35,10 @@ impl OpenAIEmbedding {
 
 #[async_trait::async_trait]
 impl Embedding for OpenAIEmbedding {
+    fn model(&self) -> String {
+        self.llm.embedding_model()
+    }
+
     async fn embed_query(&self, input: &str) -> Result<Vec<f32>> {
         let inputs = vec![input.to_string()];
         let result = self.llm.embedding(inputs).await?;
@@ -0,0 +1,225 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::Result;
+use log::info;
+
+use crate::Document;
+use crate::DocumentLoader;
+use crate::DocumentPath;
+use crate::DocumentSplitter;
+use crate::Documents;
+use crate::IndexManifest;
+use crate::ManifestEntry;
+use crate::UpsertPlan;
+use crate::VectorStore;
+
+// The files and the chunks an indexing run changed, or would change for a dry run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexReport {
+    pub dry_run: bool,
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub deleted: Vec<String>,
+    pub unchanged: usize,
+    pub chunks_added: usize,
+    // The stored chunks kept with new metadata, such as moved lines.
+    pub chunks_updated: usize,
+    pub chunks_deleted: usize,
+    pub chunks_unchanged: usize,
+}
+
+impl IndexReport {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.deleted.is_empty()
+    }
+}
+
+impl fmt::Display for IndexReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for path in &self.added {
+            writeln!(f, "+ {}", path)?;
+        }
+        for path in &self.changed {
+            writeln!(f, "~ {}", path)?;
+        }
+        for path in &self.deleted {
+            writeln!(f, "- {}", path)?;
+        }
+        write!(
+            f,
+            "{}{} added, {} changed, {} deleted, {} unchanged files; {} chunks added, {} updated, {} deleted, {} unchanged",
+            if self.dry_run { "(dry run) " } else { "" },
+            self.added.len(),
+            self.changed.len(),
+            self.deleted.len(),
+            self.unchanged,
+            self.chunks_added,
+            self.chunks_updated,
+            self.chunks_deleted,
+            self.chunks_unchanged
+        )
+    }
+}
+
+// Indexes the documents of a loader into a vector store, embedding only what changed since the
+// previous run. The manifest file remembers the hash and the chunks of every file indexed, the
+// chunks of the changed files are upserted: the unchanged ones are kept, only their metadata is updated.
+// It belongs to one source, the files the loader doesn't return anymore are deleted from the store.
+//
+//   let indexer = Indexer::create(directory_loader, store, "./index.json")
+//       .with_splitter(Arc::new(MarkdownSplitter::create()));
+//   println!("{}", indexer.plan("docs/").await?);
+//   indexer.index("docs/").await?;
+pub struct Indexer {
+    loader: Arc<dyn DocumentLoader>,
+    splitter: Option<Arc<dyn DocumentSplitter>>,
+    store: Arc<dyn VectorStore>,
+    manifest: String,
+}
+
+impl Indexer {
+    pub fn create(
+        loader: Arc<dyn DocumentLoader>,
+        store: Arc<dyn VectorStore>,
+        manifest: &str,
+    ) -> Self {
+        Indexer {
+            loader,
+            splitter: None,
+            store,
+            manifest: manifest.to_string(),
+        }
+    }
+
+    // The documents are stored as loaded without a splitter.
+    pub fn with_splitter(mut self, splitter: Arc<dyn DocumentSplitter>) -> Self {
+        self.splitter = Some(splitter);
+        self
+    }
+
+    // The report of what `index` would do, nothing is embedded or written.
+    pub async fn plan(&self, path: &str) -> Result<IndexReport> {
+        self.run(path, true).await
+    }
+
+    pub async fn index(&self, path: &str) -> Result<IndexReport> {
+        self.run(path, false).await
+    }
+
+    async fn run(&self, path: &str, dry_run: bool) -> Result<IndexReport> {
+        let mut manifest = IndexManifest::load(&self.manifest)?;
+        let documents = self.loader.load(DocumentPath::from_string(path)).await?;
+
+        let mut files: BTreeMap<String, Vec<Document>> = BTreeMap::new();
+        for document in &documents {
+            files
+                .entry(document.path.clone())
+                .or_default()
+                .push(document);
+        }
+
+        let mut report = IndexReport {
+            dry_run,
+            ..Default::default()
+        };
+        let chunks = Documents::create();
+        let mut chunk_files = vec![];
+        for (file, documents) in files.iter() {
+            let hash = file_hash(documents);
+            match manifest.files.get(file) {
+                Some(entry) if entry.hash == hash => {
+                    report.unchanged += 1;
+                    report.chunks_unchanged += entry.chunks.len();
+                    continue;
+                }
+                Some(_) => report.changed.push(file.clone()),
+                None => report.added.push(file.clone()),
+            }
+
+            let file_chunks = self.split(documents)?;
+            chunk_files.push((file.clone(), hash, file_chunks.len()));
+            for chunk in file_chunks.iter() {
+                chunks.push(chunk);
+            }
+        }
+        for file in manifest.files.keys() {
+            if !files.contains_key(file) {
+                report.deleted.push(file.clone());
+            }
+        }
+
+        // The changed files are upserted, the stored chunks tell what to embed and what to delete.
+        // The files left without chunks are planned too, all their stored chunks go.
+        let paths = chunk_files.iter().map(|x| x.0.clone()).collect::<Vec<_>>();
+        let keys = self.store.document_keys(&paths).await?;
+        let plan = UpsertPlan::create(&chunks, keys);
+        let deleted = self.store.document_keys(&report.deleted).await?;
+        report.chunks_added = plan.added.len();
+        report.chunks_updated = plan.updated.len();
+        report.chunks_deleted = plan.stale.len() + deleted.len();
+        report.chunks_unchanged += chunks.len() - plan.added.len() - plan.updated.len();
+        info!("index {}: {}", path, report);
+        if dry_run || report.is_empty() {
+            return Ok(report);
+        }
+
+        // The upsert and the deletes can be run again, a failure before the manifest is saved
+        // never duplicates the chunks: the next run finds them stored.
+        let uuids = if chunks.is_empty() {
+            vec![]
+        } else {
+            self.store.upsert_documents(&chunks).await?
+        };
+        let emptied = chunk_files.iter().filter(|x| x.2 == 0).map(|x| &x.0);
+        for file in report.deleted.iter().chain(emptied) {
+            self.store.delete_by_path(file).await?;
+        }
+
+        let mut uuids = uuids.into_iter();
+        for (file, hash, len) in chunk_files {
+            manifest.files.insert(file, ManifestEntry {
+                hash,
+                chunks: uuids.by_ref().take(len).collect(),
+            });
+        }
+        for file in &report.deleted {
+            manifest.files.remove(file);
+        }
+        manifest.save(&self.manifest)?;
+
+        Ok(report)
+    }
+
+    fn split(&self, documents: &[Document]) -> Result<Documents> {
+        let documents = Documents::from(documents.to_vec());
+        match &self.splitter {
+            Some(splitter) => splitter.split_documents(&documents),
+            None => Ok(documents),
+        }
+    }
+}
+
+// The metadata, such as the modified time, doesn't change the hash.
+fn file_hash(documents: &[Document]) -> String {
+    let mut context = md5::Context::new();
+    for document in documents {
+        context.consume(document.content_md5.as_bytes());
+    }
+    format!("{:x}", context.compute())
+}
@@ -12,16 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod caches;
 mod common;
 mod embeddings;
+mod indexers;
 mod llms;
 mod loaders;
 mod memory;
 mod prompts;
 mod vector_stores;
 
+pub use caches::*;
 pub use common::*;
 pub use embeddings::*;
+pub use indexers::*;
 pub use llms::*;
 pub use loaders::*;
 pub use memory::*;
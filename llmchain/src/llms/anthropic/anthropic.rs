@@ -0,0 +1,270 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+use anyhow::bail;
+use anyhow::Result;
+use log::warn;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error_for_status;
+use crate::messages_stream;
+use crate::ChatMessage;
+use crate::ChatRole;
+use crate::EmbeddingResult;
+use crate::GenerateOptions;
+use crate::GenerateResult;
+use crate::GenerateStream;
+use crate::ModelInfo;
+use crate::ModelRegistry;
+use crate::UsageTracker;
+use crate::LLM;
+
+pub enum AnthropicModel {
+    Claude35Sonnet,
+    Claude35Haiku,
+    Claude3Opus,
+    Claude3Haiku,
+}
+
+impl AnthropicModel {
+    pub fn info(&self) -> ModelInfo {
+        ModelRegistry::global().get_or_default(&self.to_string())
+    }
+}
+
+impl Display for AnthropicModel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnthropicModel::Claude35Sonnet => write!(f, "claude-3-5-sonnet-20241022"),
+            AnthropicModel::Claude35Haiku => write!(f, "claude-3-5-haiku-20241022"),
+            AnthropicModel::Claude3Opus => write!(f, "claude-3-opus-20240229"),
+            AnthropicModel::Claude3Haiku => write!(f, "claude-3-haiku-20240307"),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AnthropicMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct AnthropicRequest {
+    model: String,
+    max_tokens: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<AnthropicMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct AnthropicContent {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct AnthropicUsage {
+    input_tokens: u32,
+    output_tokens: u32,
+}
+
+#[derive(Deserialize)]
+struct AnthropicResponse {
+    content: Vec<AnthropicContent>,
+    usage: AnthropicUsage,
+}
+
+// The Anthropic Messages API, https://docs.anthropic.com/en/api/messages
+pub struct Anthropic {
+    api_base: RwLock<String>,
+    api_key: String,
+    api_version: String,
+    http_client: reqwest::Client,
+
+    // The maximum number of tokens allowed for the generated answer, the API requires one.
+    max_tokens: RwLock<u16>,
+    // The sampling temperature, between 0 and 1, the default of the API if unset.
+    temperature: RwLock<Option<f32>>,
+
+    generate_model: RwLock<String>,
+
+    // Every call reports its token usage into the tracker, if any.
+    usage_tracker: RwLock<Option<Arc<UsageTracker>>>,
+}
+
+impl Anthropic {
+    pub fn create(api_key: &str) -> Arc<Self> {
+        Arc::new(Anthropic {
+            api_base: RwLock::new("https://api.anthropic.com".to_string()),
+            api_key: api_key.to_string(),
+            api_version: "2023-06-01".to_string(),
+            http_client: reqwest::Client::new(),
+            max_tokens: RwLock::new(4096),
+            temperature: RwLock::new(None),
+            generate_model: RwLock::new(AnthropicModel::Claude35Sonnet.to_string()),
+            usage_tracker: RwLock::new(None),
+        })
+    }
+
+    pub fn with_api_base(self: &Arc<Self>, api_base: &str) -> Arc<Self> {
+        *self.api_base.write() = api_base.trim_end_matches('/').to_string();
+        self.clone()
+    }
+
+    pub fn with_max_tokens(self: &Arc<Self>, max_tokens: u16) -> Arc<Self> {
+        *self.max_tokens.write() = max_tokens;
+        self.clone()
+    }
+
+    pub fn with_temperature(self: &Arc<Self>, temperature: f32) -> Arc<Self> {
+        *self.temperature.write() = Some(temperature);
+        self.clone()
+    }
+
+    pub fn with_generate_model(self: &Arc<Self>, model: AnthropicModel) -> Arc<Self> {
+        *self.generate_model.write() = model.to_string();
+        self.clone()
+    }
+
+    pub fn with_usage_tracker(self: &Arc<Self>, tracker: Arc<UsageTracker>) -> Arc<Self> {
+        *self.usage_tracker.write() = Some(tracker);
+        self.clone()
+    }
+
+    fn request(
+        &self,
+        messages: &[ChatMessage],
+        options: &GenerateOptions,
+        stream: bool,
+    ) -> Result<AnthropicRequest> {
+        if options.n.is_some_and(|n| n > 1) {
+            warn!("Anthropic generates one answer per request, n is ignored");
+        }
+        if options.seed.is_some() {
+            warn!("Anthropic has no seed, seed is ignored");
+        }
+
+        // The system prompt is not a message of the conversation.
+        let mut system = vec![];
+        let mut anthropic_messages = vec![];
+        for message in messages {
+            let role = match message.role {
+                ChatRole::System => {
+                    system.push(message.content.clone());
+                    continue;
+                }
+                ChatRole::User => "user",
+                ChatRole::Assistant => "assistant",
+                ChatRole::Tool => bail!("tool messages are not supported by Anthropic"),
+            };
+            anthropic_messages.push(AnthropicMessage {
+                role: role.to_string(),
+                content: message.content.clone(),
+            });
+        }
+
+        Ok(AnthropicRequest {
+            model: options
+                .model
+                .clone()
+                .unwrap_or(self.generate_model.read().clone()),
+            max_tokens: options.max_tokens.unwrap_or(*self.max_tokens.read()),
+            system: (!system.is_empty()).then(|| system.join("\n")),
+            messages: anthropic_messages,
+            stop_sequences: options.stop.clone(),
+            temperature: options.temperature.or(*self.temperature.read()),
+            top_p: options.top_p,
+            stream,
+        })
+    }
+
+    async fn send(&self, request: &AnthropicRequest) -> Result<reqwest::Response> {
+        let response = self
+            .http_client
+            .post(format!("{}/v1/messages", self.api_base.read()))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", &self.api_version)
+            .json(request)
+            .send()
+            .await?;
+        error_for_status(response).await
+    }
+}
+
+#[async_trait::async_trait]
+impl LLM for Anthropic {
+    fn generate_model(&self) -> String {
+        self.generate_model.read().clone()
+    }
+
+    async fn embedding(&self, _inputs: Vec<String>) -> Result<EmbeddingResult> {
+        bail!("embedding is not supported by Anthropic")
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        let request = self.request(&messages, options, false)?;
+        let response: AnthropicResponse = self.send(&request).await?.json().await?;
+
+        let generation = response
+            .content
+            .iter()
+            .map(|content| content.text.as_str())
+            .collect::<String>();
+        let generate_result = GenerateResult {
+            prompt_tokens: response.usage.input_tokens,
+            completion_tokens: response.usage.output_tokens,
+            total_tokens: response.usage.input_tokens + response.usage.output_tokens,
+            generations: vec![generation.clone()],
+            generation,
+            tool_calls: vec![],
+            provider: self.name(),
+        };
+        if let Some(tracker) = self.usage_tracker.read().as_ref() {
+            tracker.record_generation(&request.model, &generate_result);
+        }
+        Ok(generate_result)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateStream> {
+        let request = self.request(&messages, options, true)?;
+        let stream = messages_stream(self.send(&request).await?);
+        Ok(match self.usage_tracker.read().as_ref() {
+            Some(tracker) => tracker.track_stream(&request.model, stream),
+            None => stream,
+        })
+    }
+}
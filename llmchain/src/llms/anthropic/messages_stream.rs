@@ -0,0 +1,136 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::anyhow;
+use anyhow::Result;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use serde_json::Value;
+
+use crate::GenerateChunk;
+use crate::GenerateStream;
+use crate::HttpError;
+
+struct MessagesStreamState {
+    response: BoxStream<'static, reqwest::Result<Vec<u8>>>,
+    buffer: Vec<u8>,
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    finished: bool,
+}
+
+impl MessagesStreamState {
+    // Pops the next complete event of the buffer, the events are separated by a blank line.
+    fn next_event(&mut self) -> Option<String> {
+        let pos = self.buffer.windows(2).position(|w| w == b"\n\n")?;
+        let event = self.buffer.drain(..pos + 2).collect::<Vec<_>>();
+        Some(String::from_utf8_lossy(&event).to_string())
+    }
+
+    fn handle_event(&mut self, event: &str) -> Result<Option<GenerateChunk>> {
+        let data = event
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(|line| line.trim_start())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if data.is_empty() {
+            return Ok(None);
+        }
+
+        let data: Value = serde_json::from_str(&data)?;
+        match data["type"].as_str().unwrap_or_default() {
+            "message_start" => {
+                let usage = &data["message"]["usage"];
+                self.prompt_tokens = usage["input_tokens"].as_u64().unwrap_or_default() as u32;
+                self.completion_tokens = usage["output_tokens"].as_u64().unwrap_or_default() as u32;
+                Ok(None)
+            }
+            "content_block_delta" => match data["delta"]["text"].as_str() {
+                Some(text) if !text.is_empty() => Ok(Some(GenerateChunk::Delta(text.to_string()))),
+                _ => Ok(None),
+            },
+            // The output tokens are cumulative.
+            "message_delta" => {
+                if let Some(tokens) = data["usage"]["output_tokens"].as_u64() {
+                    self.completion_tokens = tokens as u32;
+                }
+                Ok(None)
+            }
+            "message_stop" => {
+                self.finished = true;
+                Ok(Some(GenerateChunk::Usage {
+                    prompt_tokens: self.prompt_tokens,
+                    completion_tokens: self.completion_tokens,
+                    total_tokens: self.prompt_tokens + self.completion_tokens,
+                }))
+            }
+            "error" => {
+                let error = &data["error"];
+                let message = error["message"].as_str().unwrap_or_default();
+                match error["type"].as_str() {
+                    // The same errors as the HTTP 529 and 429 of a request.
+                    Some("overloaded_error") => Err(HttpError::create(529, message).into()),
+                    Some("rate_limit_error") => Err(HttpError::create(429, message).into()),
+                    Some("api_error") => Err(HttpError::create(500, message).into()),
+                    error_type => Err(anyhow!("{}: {}", error_type.unwrap_or("error"), message)),
+                }
+            }
+            // ping, content_block_start, content_block_stop.
+            _ => Ok(None),
+        }
+    }
+}
+
+// Turn the Messages API SSE stream into deltas, followed by the usage item of message_stop.
+pub(crate) fn messages_stream(response: reqwest::Response) -> GenerateStream {
+    let state = MessagesStreamState {
+        response: response
+            .bytes_stream()
+            .map(|bytes| bytes.map(|bytes| bytes.to_vec()))
+            .boxed(),
+        buffer: vec![],
+        prompt_tokens: 0,
+        completion_tokens: 0,
+        finished: false,
+    };
+
+    Box::pin(futures::stream::unfold(state, |mut state| async move {
+        while !state.finished {
+            if let Some(event) = state.next_event() {
+                match state.handle_event(&event) {
+                    Ok(Some(chunk)) => return Some((Ok(chunk), state)),
+                    Ok(None) => continue,
+                    Err(e) => {
+                        state.finished = true;
+                        return Some((Err(e), state));
+                    }
+                }
+            }
+
+            match state.response.next().await {
+                Some(Ok(bytes)) => state.buffer.extend(bytes.iter().filter(|b| **b != b'\r')),
+                Some(Err(e)) => {
+                    state.finished = true;
+                    return Some((Err(e.into()), state));
+                }
+                None => {
+                    state.finished = true;
+                    return Some((Err(anyhow!("stream closed before message_stop")), state));
+                }
+            }
+        }
+        None
+    }))
+}
@@ -13,20 +13,36 @@
 // limitations under the License.
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use async_openai::config::AzureConfig;
-use async_openai::types::ChatCompletionRequestMessageArgs;
+use async_openai::types::CreateChatCompletionRequest;
 use async_openai::types::CreateChatCompletionRequestArgs;
 use async_openai::types::CreateEmbeddingRequestArgs;
-use async_openai::types::Role;
 use async_openai::Client;
+use backoff::ExponentialBackoff;
 use parking_lot::RwLock;
 
+use crate::chat_completion_functions;
+use crate::chat_completion_messages;
+use crate::chat_completion_options;
+use crate::chat_completion_prompt_tokens;
+use crate::chat_completion_stream;
+use crate::chat_completion_tool_calls;
+use crate::completion_budget;
+use crate::seeded_chat_completion;
+use crate::seeded_chat_completion_stream;
+use crate::ChatMessage;
 use crate::EmbeddingResult;
+use crate::GenerateOptions;
 use crate::GenerateResult;
+use crate::GenerateStream;
+use crate::ModelRegistry;
 use crate::OpenAIEmbeddingModel;
 use crate::OpenAIGenerateModel;
+use crate::Tool;
+use crate::UsageTracker;
 use crate::LLM;
 
 pub struct AzureOpenAI {
@@ -36,8 +52,11 @@ pub struct AzureOpenAI {
     deployment_id: String,
 
     // The maximum number of tokens allowed for the generated answer.
-    // By default, the number of tokens the model can return will be (4095 - prompt tokens).
-    max_tokens: RwLock<u16>,
+    // By default, the answer can use the context window left by the prompt, up to the output cap of the model.
+    max_tokens: RwLock<Option<u16>>,
+
+    // The tokens kept for the answer, a prompt which leaves less of the context window is rejected.
+    reserved_completion_tokens: RwLock<usize>,
 
     // What sampling temperature to use, between 0 and 2. Higher values like 0.8 will make the output more random, while lower values like 0.2 will make it more focused and deterministic.
     // We generally recommend altering this or top_p but not both.
@@ -45,6 +64,16 @@ pub struct AzureOpenAI {
 
     embedding_model: RwLock<OpenAIEmbeddingModel>,
     generate_model: RwLock<OpenAIGenerateModel>,
+
+    // Every call reports its token usage into the tracker, if any.
+    usage_tracker: RwLock<Option<Arc<UsageTracker>>>,
+
+    // The client retries the rate limited requests by itself,
+    // turn it off when a `RetryingLLM` owns the retries.
+    rate_limit_backoff: RwLock<bool>,
+
+    // The client sending the requests, inject one to set the timeouts, a proxy or the TLS.
+    http_client: RwLock<reqwest::Client>,
 }
 
 impl AzureOpenAI {
@@ -54,15 +83,24 @@ impl AzureOpenAI {
             api_key: api_key.to_string(),
             api_version: "2023-03-15-preview".to_string(),
             deployment_id: deployment_id.to_string(),
-            max_tokens: RwLock::new(4095),
+            max_tokens: RwLock::new(None),
+            reserved_completion_tokens: RwLock::new(256),
             temperature: RwLock::new(1.0),
             embedding_model: RwLock::new(OpenAIEmbeddingModel::TextEmbeddingAda002),
             generate_model: RwLock::new(OpenAIGenerateModel::Gpt35),
+            usage_tracker: RwLock::new(None),
+            rate_limit_backoff: RwLock::new(true),
+            http_client: RwLock::new(reqwest::Client::new()),
         })
     }
 
     pub fn with_max_tokens(self: &Arc<Self>, max_tokens: u16) -> Arc<Self> {
-        *self.max_tokens.write() = max_tokens;
+        *self.max_tokens.write() = Some(max_tokens);
+        self.clone()
+    }
+
+    pub fn with_reserved_completion_tokens(self: &Arc<Self>, tokens: usize) -> Arc<Self> {
+        *self.reserved_completion_tokens.write() = tokens;
         self.clone()
     }
 
@@ -81,54 +119,143 @@ impl AzureOpenAI {
         self.clone()
     }
 
-    pub fn get_client(&self) -> Client<AzureConfig> {
-        let conf = AzureConfig::new()
+    pub fn with_usage_tracker(self: &Arc<Self>, tracker: Arc<UsageTracker>) -> Arc<Self> {
+        *self.usage_tracker.write() = Some(tracker);
+        self.clone()
+    }
+
+    pub fn with_rate_limit_backoff(self: &Arc<Self>, backoff: bool) -> Arc<Self> {
+        *self.rate_limit_backoff.write() = backoff;
+        self.clone()
+    }
+
+    pub fn with_http_client(self: &Arc<Self>, http_client: reqwest::Client) -> Arc<Self> {
+        *self.http_client.write() = http_client;
+        self.clone()
+    }
+
+    fn config(&self) -> AzureConfig {
+        AzureConfig::new()
             .with_api_key(&self.api_key)
             .with_api_base(&self.api_base)
             .with_deployment_id(&self.deployment_id)
-            .with_api_version(&self.api_version);
-        Client::with_config(conf)
+            .with_api_version(&self.api_version)
+    }
+
+    pub fn get_client(&self) -> Client<AzureConfig> {
+        let client =
+            Client::with_config(self.config()).with_http_client(self.http_client.read().clone());
+        if *self.rate_limit_backoff.read() {
+            client
+        } else {
+            client.with_backoff(ExponentialBackoff {
+                max_elapsed_time: Some(Duration::ZERO),
+                ..Default::default()
+            })
+        }
+    }
+
+    fn chat_request(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[Tool],
+        options: &GenerateOptions,
+    ) -> Result<CreateChatCompletionRequest> {
+        let model = options
+            .model
+            .clone()
+            .unwrap_or(self.generate_model.read().to_string());
+        let model_info = ModelRegistry::global().get_or_default(&model);
+        let budget = completion_budget(
+            chat_completion_prompt_tokens(model_info.tokenizer, messages),
+            model_info.context_length,
+            model_info.max_output_tokens,
+            *self.reserved_completion_tokens.read(),
+        )?;
+        let max_tokens = options
+            .max_tokens
+            .or(*self.max_tokens.read())
+            .map_or(budget, |max_tokens| max_tokens.min(budget));
+
+        let mut request = CreateChatCompletionRequestArgs::default();
+        request
+            .max_tokens(max_tokens)
+            .model(model)
+            .temperature(options.temperature.unwrap_or(*self.temperature.read()))
+            .messages(chat_completion_messages(messages));
+        chat_completion_options(&mut request, options);
+        if !tools.is_empty() {
+            request.functions(chat_completion_functions(tools));
+        }
+        Ok(request.build()?)
     }
 }
 
 #[async_trait::async_trait]
 impl LLM for AzureOpenAI {
+    fn generate_model(&self) -> String {
+        self.generate_model.read().to_string()
+    }
+
+    fn embedding_model(&self) -> String {
+        self.embedding_model.read().to_string()
+    }
+
     async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+        let model = self.embedding_model.read().to_string();
         let request = CreateEmbeddingRequestArgs::default()
-            .model(&self.embedding_model.read().to_string())
+            .model(model.clone())
             .input(inputs)
             .build()?;
 
         let client = self.get_client();
         let response = client.embeddings().create(request).await?;
-        let mut embeddings = Vec::with_capacity(response.data.len());
-        for embedding in &response.data {
-            embeddings.push(embedding.embedding.clone());
-        }
+        // In the order of the inputs, whatever the order of the answer.
+        let mut data = response.data;
+        data.sort_by_key(|x| x.index);
+        let embeddings = data.into_iter().map(|x| x.embedding).collect::<Vec<_>>();
 
         let embedding_result = EmbeddingResult {
             prompt_tokens: response.usage.prompt_tokens,
             total_tokens: response.usage.total_tokens,
             embeddings,
+            provider: self.name(),
         };
+        if let Some(tracker) = self.usage_tracker.read().as_ref() {
+            tracker.record_embedding(&model, &embedding_result);
+        }
         Ok(embedding_result)
     }
 
-    async fn generate(&self, input: &str) -> Result<GenerateResult> {
-        let request = CreateChatCompletionRequestArgs::default()
-            .max_tokens(*self.max_tokens.read() - input.len() as u16)
-            .model(&self.generate_model.read().to_string())
-            .temperature(*self.temperature.read())
-            .messages([ChatCompletionRequestMessageArgs::default()
-                .role(Role::Assistant)
-                .content(input)
-                .build()?])
-            .build()?;
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        self.chat_with_tools(messages, vec![], options).await
+    }
 
-        let client = self.get_client();
-        let response = client.chat().create(request).await?;
+    async fn chat_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<Tool>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        let request = self.chat_request(&messages, &tools, options)?;
+        let model = request.model.clone();
 
-        let mut generate_result = GenerateResult::default();
+        let response = match options.seed {
+            Some(seed) => {
+                let http_client = self.http_client.read().clone();
+                seeded_chat_completion(&self.config(), &http_client, &request, seed).await?
+            }
+            None => self.get_client().chat().create(request).await?,
+        };
+
+        let mut generate_result = GenerateResult {
+            provider: self.name(),
+            ..Default::default()
+        };
 
         // Usage.
         if let Some(usage) = response.usage {
@@ -139,8 +266,41 @@ impl LLM for AzureOpenAI {
 
         if let Some(choice) = response.choices.first() {
             generate_result.generation = choice.message.content.clone().unwrap_or_default();
+            generate_result.tool_calls = chat_completion_tool_calls(&choice.message)?;
         }
+        generate_result.generations = response
+            .choices
+            .iter()
+            .map(|choice| choice.message.content.clone().unwrap_or_default())
+            .collect();
 
+        if let Some(tracker) = self.usage_tracker.read().as_ref() {
+            tracker.record_generation(&model, &generate_result);
+        }
         Ok(generate_result)
     }
+
+    async fn chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateStream> {
+        let request = self.chat_request(&messages, &[], options)?;
+        let model = request.model.clone();
+        let tokenizer = ModelRegistry::global().get_or_default(&model).tokenizer;
+        let prompt_tokens = chat_completion_prompt_tokens(tokenizer, &messages);
+
+        let response = match options.seed {
+            Some(seed) => {
+                let http_client = self.http_client.read().clone();
+                seeded_chat_completion_stream(&self.config(), &http_client, &request, seed).await?
+            }
+            None => self.get_client().chat().create_stream(request).await?,
+        };
+        let stream = chat_completion_stream(response, tokenizer, prompt_tokens);
+        Ok(match self.usage_tracker.read().as_ref() {
+            Some(tracker) => tracker.track_stream(&model, stream),
+            None => stream,
+        })
+    }
 }
@@ -0,0 +1,215 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use anyhow::bail;
+use anyhow::Result;
+use futures::StreamExt;
+use log::warn;
+use parking_lot::RwLock;
+use serde::Serialize;
+
+use crate::cache_get;
+use crate::cache_set;
+use crate::Cache;
+use crate::ChatMessage;
+use crate::EmbeddingResult;
+use crate::GenerateChunk;
+use crate::GenerateOptions;
+use crate::GenerateResult;
+use crate::GenerateStream;
+use crate::Tool;
+use crate::LLM;
+
+// Answers the calls already made from the cache,
+// keyed on the LLM name, its model, the namespace and the request.
+pub struct CachedLLM {
+    llm: Arc<dyn LLM>,
+    cache: Arc<dyn Cache>,
+
+    // Separates the entries of the LLMs sharing a cache beyond their model, such as two prompt versions.
+    namespace: RwLock<String>,
+}
+
+impl CachedLLM {
+    pub fn create(llm: Arc<dyn LLM>, cache: Arc<dyn Cache>) -> Arc<Self> {
+        Arc::new(CachedLLM {
+            llm,
+            cache,
+            namespace: RwLock::new("".to_string()),
+        })
+    }
+
+    pub fn with_namespace(self: &Arc<Self>, namespace: &str) -> Arc<Self> {
+        *self.namespace.write() = namespace.to_string();
+        self.clone()
+    }
+
+    // The model of the LLM is part of the key, `GenerateOptions::model` is with the request.
+    fn key<T: Serialize>(&self, kind: &str, request: &T) -> Result<String> {
+        let model = match kind {
+            "embedding" => self.llm.embedding_model(),
+            _ => self.llm.generate_model(),
+        };
+        let request = serde_json::to_string(request)?;
+        let digest = md5::compute(format!(
+            "{}\n{}\n{}\n{}",
+            self.llm.name(),
+            model,
+            self.namespace.read(),
+            request
+        ));
+        Ok(format!("{}-{:x}", kind, digest))
+    }
+}
+
+#[async_trait::async_trait]
+impl LLM for CachedLLM {
+    fn name(&self) -> String {
+        self.llm.name()
+    }
+
+    fn generate_model(&self) -> String {
+        self.llm.generate_model()
+    }
+
+    fn embedding_model(&self) -> String {
+        self.llm.embedding_model()
+    }
+
+    // Every input is cached on its own, only the missing ones are sent to the LLM.
+    async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+        let mut keys = Vec::with_capacity(inputs.len());
+        let mut embeddings = Vec::with_capacity(inputs.len());
+        let mut missing = vec![];
+        for input in &inputs {
+            let key = self.key("embedding", input)?;
+            let embedding: Option<Vec<f32>> = cache_get(self.cache.as_ref(), &key)?;
+            if embedding.is_none() {
+                missing.push(input.clone());
+            }
+            keys.push(key);
+            embeddings.push(embedding);
+        }
+
+        let mut result = EmbeddingResult {
+            provider: self.llm.name(),
+            ..Default::default()
+        };
+        if !missing.is_empty() {
+            let missing_count = missing.len();
+            let missing_result = self.llm.embedding(missing).await?;
+            // Nothing is cached from a short answer, the vectors would be assigned to the wrong inputs.
+            if missing_result.embeddings.len() != missing_count {
+                bail!(
+                    "{} returned {} embeddings for {} inputs",
+                    missing_result.provider,
+                    missing_result.embeddings.len(),
+                    missing_count
+                );
+            }
+
+            let mut missing_embeddings = missing_result.embeddings.into_iter();
+            for (key, embedding) in keys.iter().zip(embeddings.iter_mut()) {
+                if embedding.is_none() {
+                    *embedding = missing_embeddings.next();
+                    cache_set(self.cache.as_ref(), key, embedding)?;
+                }
+            }
+            result.prompt_tokens = missing_result.prompt_tokens;
+            result.total_tokens = missing_result.total_tokens;
+            result.provider = missing_result.provider;
+        }
+        result.embeddings = embeddings.into_iter().flatten().collect();
+
+        Ok(result)
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        self.chat_with_tools(messages, vec![], options).await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<Tool>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        let key = self.key("chat", &(&messages, &tools, options))?;
+        if let Some(result) = cache_get(self.cache.as_ref(), &key)? {
+            return Ok(result);
+        }
+
+        let result = if tools.is_empty() {
+            self.llm.chat(messages, options).await?
+        } else {
+            self.llm.chat_with_tools(messages, tools, options).await?
+        };
+        cache_set(self.cache.as_ref(), &key, &result)?;
+        Ok(result)
+    }
+
+    // A cached generation is replayed as a single delta,
+    // a new one is cached once the stream is over.
+    async fn chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateStream> {
+        let key = self.key("chat", &(&messages, &Vec::<Tool>::new(), options))?;
+        if let Some(result) = cache_get::<GenerateResult>(self.cache.as_ref(), &key)? {
+            let chunks = vec![
+                Ok(GenerateChunk::Delta(result.generation)),
+                Ok(GenerateChunk::Usage {
+                    prompt_tokens: result.prompt_tokens,
+                    completion_tokens: result.completion_tokens,
+                    total_tokens: result.total_tokens,
+                }),
+            ];
+            return Ok(Box::pin(futures::stream::iter(chunks)));
+        }
+
+        let stream = self.llm.chat_stream(messages, options).await?;
+        let cache = self.cache.clone();
+        let provider = self.llm.name();
+        let mut generation = String::new();
+        Ok(Box::pin(stream.inspect(move |chunk| match chunk {
+            Ok(GenerateChunk::Delta(delta)) => generation.push_str(delta),
+            Ok(GenerateChunk::Usage {
+                prompt_tokens,
+                completion_tokens,
+                total_tokens,
+            }) => {
+                let result = GenerateResult {
+                    prompt_tokens: *prompt_tokens,
+                    completion_tokens: *completion_tokens,
+                    total_tokens: *total_tokens,
+                    generation: generation.clone(),
+                    generations: vec![generation.clone()],
+                    tool_calls: vec![],
+                    provider: provider.clone(),
+                };
+                if let Err(e) = cache_set(cache.as_ref(), &key, &result) {
+                    warn!("cache the generation of {} error: {}", key, e);
+                }
+            }
+            Err(_) => {}
+        })))
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ToolCall;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+    // The result of a tool(function) call, `name` is the tool which produced it.
+    Tool,
+}
+
+impl Display for ChatRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChatRole::System => write!(f, "System"),
+            ChatRole::User => write!(f, "User"),
+            ChatRole::Assistant => write!(f, "Assistant"),
+            ChatRole::Tool => write!(f, "Tool"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    pub content: String,
+    pub name: Option<String>,
+    // The tool call the assistant asked for, to feed back along with its result.
+    pub tool_call: Option<ToolCall>,
+}
+
+impl ChatMessage {
+    pub fn create(role: ChatRole, content: &str) -> Self {
+        ChatMessage {
+            role,
+            content: content.to_string(),
+            name: None,
+            tool_call: None,
+        }
+    }
+
+    pub fn system(content: &str) -> Self {
+        Self::create(ChatRole::System, content)
+    }
+
+    pub fn user(content: &str) -> Self {
+        Self::create(ChatRole::User, content)
+    }
+
+    pub fn assistant(content: &str) -> Self {
+        Self::create(ChatRole::Assistant, content)
+    }
+
+    pub fn assistant_tool_call(tool_call: ToolCall) -> Self {
+        let mut message = Self::create(ChatRole::Assistant, "");
+        message.tool_call = Some(tool_call);
+        message
+    }
+
+    pub fn tool(name: &str, content: &str) -> Self {
+        Self::create(ChatRole::Tool, content).with_name(name)
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    // Flatten a conversation into a single prompt, for the backends which have no roles.
+    // A lone user message is sent as it is.
+    pub fn flatten(messages: &[ChatMessage]) -> String {
+        if let [message] = messages {
+            if message.role == ChatRole::User {
+                return message.content.clone();
+            }
+        }
+
+        let mut prompt = String::new();
+        for message in messages {
+            match &message.name {
+                Some(name) => prompt.push_str(&format!("{}({})", message.role, name)),
+                None => prompt.push_str(&message.role.to_string()),
+            }
+            prompt.push_str(": ");
+            prompt.push_str(&message.content);
+            if let Some(tool_call) = &message.tool_call {
+                prompt.push_str(&format!("{}({})", tool_call.name, tool_call.arguments));
+            }
+            prompt.push('\n');
+        }
+        prompt.push_str(&format!("{}: ", ChatRole::Assistant));
+        prompt
+    }
+}
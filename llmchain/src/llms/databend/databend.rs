@@ -17,66 +17,123 @@ use std::sync::Arc;
 use anyhow::anyhow;
 use anyhow::Result;
 use databend_driver::Client;
+use futures::StreamExt;
 use log::info;
-use tokio_stream::StreamExt;
+use parking_lot::RwLock;
 
-use crate::escape_sql_string;
+use crate::quote_sql_string;
+use crate::ChatMessage;
 use crate::EmbeddingResult;
+use crate::GenerateOptions;
 use crate::GenerateResult;
+use crate::Tokenizer;
+use crate::UsageTracker;
 use crate::LLM;
 
+// The models behind the Databend AI functions, as reported to the usage tracker.
+const EMBEDDING_MODEL: &str = "databend-ai-embedding-vector";
+const GENERATE_MODEL: &str = "databend-ai-text-completion";
+
 pub struct DatabendLLM {
     client: Client,
+    usage_tracker: RwLock<Option<Arc<UsageTracker>>>,
+
+    // The maximum number of embedding queries running at the same time, one query per input.
+    embedding_concurrency: RwLock<usize>,
 }
 
 impl DatabendLLM {
     pub fn create(dsn: &str) -> Arc<Self> {
         Arc::new(DatabendLLM {
             client: Client::new(dsn.to_string()),
+            usage_tracker: RwLock::new(None),
+            embedding_concurrency: RwLock::new(4),
         })
     }
-}
 
-#[async_trait::async_trait]
-impl LLM for DatabendLLM {
-    async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+    pub fn with_embedding_concurrency(self: &Arc<Self>, concurrency: usize) -> Arc<Self> {
+        *self.embedding_concurrency.write() = concurrency.max(1);
+        self.clone()
+    }
+
+    async fn embedding_vector(&self, input: &str) -> Result<Vec<f32>> {
         let conn = self.client.get_conn().await?;
-        let mut embeddings = vec![];
-        for (i, input) in inputs.iter().enumerate() {
-            let now = std::time::Instant::now();
-            type RowResult = (String,);
-            let mut rows = conn
-                .query_iter(&format!(
-                    "SELECT ai_embedding_vector('{}')",
-                    escape_sql_string(input)
-                ))
-                .await?;
-            while let Some(row) = rows.next().await {
+        type RowResult = (String,);
+        let mut rows = conn
+            .query_iter(&format!(
+                "SELECT ai_embedding_vector({})",
+                quote_sql_string(input)
+            ))
+            .await?;
+        match rows.next().await {
+            Some(row) => {
                 let row: RowResult = row?.try_into().map_err(|e: String| anyhow!(e))?;
-                let array_vec: Vec<f32> = serde_json::from_str(&row.0)?;
-                info!(
-                    "embedding {}/{},  time: {:?}",
-                    i + 1,
-                    inputs.len(),
-                    now.elapsed()
-                );
-                embeddings.push(array_vec);
+                Ok(serde_json::from_str(&row.0)?)
             }
+            None => Err(anyhow!("ai_embedding_vector returned no rows")),
         }
+    }
+
+    pub fn with_usage_tracker(self: &Arc<Self>, tracker: Arc<UsageTracker>) -> Arc<Self> {
+        *self.usage_tracker.write() = Some(tracker);
+        self.clone()
+    }
+}
 
-        Ok(EmbeddingResult {
-            prompt_tokens: 0,
-            total_tokens: 0,
+#[async_trait::async_trait]
+impl LLM for DatabendLLM {
+    fn generate_model(&self) -> String {
+        GENERATE_MODEL.to_string()
+    }
+
+    fn embedding_model(&self) -> String {
+        EMBEDDING_MODEL.to_string()
+    }
+
+    async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+        let concurrency = *self.embedding_concurrency.read();
+        let total = inputs.len();
+        let embeddings = futures::stream::iter(inputs.clone().into_iter().enumerate())
+            .map(|(i, input)| async move {
+                let now = std::time::Instant::now();
+                let embedding = self.embedding_vector(&input).await?;
+                info!("embedding {}/{},  time: {:?}", i + 1, total, now.elapsed());
+                Ok::<_, anyhow::Error>(embedding)
+            })
+            .buffered(concurrency)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+
+        // The AI functions don't report the usage, it is estimated with the tokenizer.
+        let tokenizer = Tokenizer::default();
+        let prompt_tokens = inputs.iter().map(|x| tokenizer.count(x)).sum::<usize>() as u32;
+        let embedding_result = EmbeddingResult {
+            prompt_tokens,
+            total_tokens: prompt_tokens,
             embeddings,
-        })
+            provider: self.name(),
+        };
+        if let Some(tracker) = self.usage_tracker.read().as_ref() {
+            tracker.record_embedding(EMBEDDING_MODEL, &embedding_result);
+        }
+        Ok(embedding_result)
     }
 
-    async fn generate(&self, input: &str) -> Result<GenerateResult> {
+    // ai_text_completion has no roles nor generation options,
+    // the conversation is flattened into one prompt and the options are ignored.
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        _options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        let prompt = ChatMessage::flatten(&messages);
         let conn = self.client.get_conn().await?;
         let row = conn
             .query_row(&format!(
-                "SELECT ai_text_completion('{}')",
-                escape_sql_string(input)
+                "SELECT ai_text_completion({})",
+                quote_sql_string(&prompt)
             ))
             .await?;
 
@@ -88,11 +145,21 @@ impl LLM for DatabendLLM {
             None => "".to_string(),
         };
 
-        Ok(GenerateResult {
-            prompt_tokens: 0,
-            completion_tokens: 0,
-            total_tokens: 0,
+        let tokenizer = Tokenizer::default();
+        let prompt_tokens = tokenizer.count(&prompt) as u32;
+        let completion_tokens = tokenizer.count(&generation) as u32;
+        let generate_result = GenerateResult {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            generations: vec![generation.clone()],
             generation,
-        })
+            tool_calls: vec![],
+            provider: self.name(),
+        };
+        if let Some(tracker) = self.usage_tracker.read().as_ref() {
+            tracker.record_generation(GENERATE_MODEL, &generate_result);
+        }
+        Ok(generate_result)
     }
 }
@@ -0,0 +1,167 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use futures::StreamExt;
+use log::info;
+use log::warn;
+use parking_lot::RwLock;
+
+use crate::ChatMessage;
+use crate::EmbeddingResult;
+use crate::GenerateOptions;
+use crate::GenerateResult;
+use crate::GenerateStream;
+use crate::Tool;
+use crate::LLM;
+
+// Tries the LLMs in order and answers with the first one which succeeds,
+// the name of the LLM which answered is recorded in the `provider` of the result,
+// and logged for a stream, its chunks have no provider.
+pub struct FallbackLLM {
+    llms: Vec<Arc<dyn LLM>>,
+
+    // The time given to each LLM before falling back to the next one.
+    timeout: RwLock<Option<Duration>>,
+}
+
+impl FallbackLLM {
+    pub fn create(llms: Vec<Arc<dyn LLM>>) -> Arc<Self> {
+        Arc::new(FallbackLLM {
+            llms,
+            timeout: RwLock::new(None),
+        })
+    }
+
+    pub fn with_timeout(self: &Arc<Self>, timeout: Duration) -> Arc<Self> {
+        *self.timeout.write() = Some(timeout);
+        self.clone()
+    }
+
+    async fn fallback<'a, T, F, Fut>(&'a self, call: F) -> Result<(String, T)>
+    where
+        F: Fn(&'a Arc<dyn LLM>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let timeout = *self.timeout.read();
+        let mut errors = vec![];
+        for llm in &self.llms {
+            let result = match timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, call(llm)).await {
+                    Ok(result) => result,
+                    Err(_) => Err(anyhow!("timed out after {:?}", timeout)),
+                },
+                None => call(llm).await,
+            };
+
+            match result {
+                Ok(v) => return Ok((llm.name(), v)),
+                Err(err) => {
+                    warn!(
+                        "LLM {} failed, fallback to the next one: {}",
+                        llm.name(),
+                        err
+                    );
+                    errors.push(format!("{}: {}", llm.name(), err));
+                }
+            }
+        }
+
+        Err(anyhow!("all the LLMs failed: [{}]", errors.join(", ")))
+    }
+}
+
+#[async_trait::async_trait]
+impl LLM for FallbackLLM {
+    // Any of them can answer, all of them name the model.
+    fn generate_model(&self) -> String {
+        let models = self.llms.iter().map(|x| x.generate_model());
+        models.collect::<Vec<_>>().join(",")
+    }
+
+    fn embedding_model(&self) -> String {
+        let models = self.llms.iter().map(|x| x.embedding_model());
+        models.collect::<Vec<_>>().join(",")
+    }
+
+    async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+        let inputs = &inputs;
+        let (provider, mut result) = self.fallback(|llm| llm.embedding(inputs.clone())).await?;
+        if result.provider.is_empty() {
+            result.provider = provider;
+        }
+        Ok(result)
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        let messages = &messages;
+        let (provider, mut result) = self
+            .fallback(|llm| llm.chat(messages.clone(), options))
+            .await?;
+        if result.provider.is_empty() {
+            result.provider = provider;
+        }
+        Ok(result)
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<Tool>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        let messages = &messages;
+        let tools = &tools;
+        let (provider, mut result) = self
+            .fallback(|llm| llm.chat_with_tools(messages.clone(), tools.clone(), options))
+            .await?;
+        if result.provider.is_empty() {
+            result.provider = provider;
+        }
+        Ok(result)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateStream> {
+        let messages = &messages;
+        let (provider, stream) = self
+            .fallback(|llm| async move {
+                let mut stream = llm.chat_stream(messages.clone(), options).await?;
+                // Only the start of the stream falls back, up to its first item.
+                match stream.next().await {
+                    Some(Ok(chunk)) => {
+                        let first = futures::stream::once(async { Ok(chunk) });
+                        Ok(Box::pin(first.chain(stream)) as GenerateStream)
+                    }
+                    Some(Err(err)) => Err(err),
+                    None => Ok(stream),
+                }
+            })
+            .await?;
+        info!("LLM {} answers the stream", provider);
+        Ok(stream)
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::time::Duration;
+
+use anyhow::Result;
+use reqwest::header::HeaderMap;
+use reqwest::Response;
+
+// An error status returned by an LLM HTTP API.
+// It keeps the status and the `Retry-After` hint so that a caller can decide to retry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpError {
+    pub status: u16,
+    pub message: String,
+    pub retry_after: Option<Duration>,
+}
+
+impl HttpError {
+    pub fn create(status: u16, message: &str) -> Self {
+        HttpError {
+            status,
+            message: message.to_string(),
+            retry_after: None,
+        }
+    }
+
+    pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = Some(retry_after);
+        self
+    }
+
+    // Takes the retry delay from the `retry-after-ms` or `retry-after` response headers.
+    pub fn with_headers(mut self, headers: &HeaderMap) -> Self {
+        if let Some(retry_after) = retry_after(headers) {
+            self.retry_after = Some(retry_after);
+        }
+        self
+    }
+
+    // Timeouts, rate limits and server errors are worth retrying.
+    pub fn is_transient(&self) -> bool {
+        matches!(self.status, 408 | 409 | 429) || self.status >= 500
+    }
+}
+
+impl Display for HttpError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "http status {}: {}", self.status, self.message)
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+// Parses the `retry-after-ms` and `retry-after` headers, only the delay in seconds form is supported.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|v| v.is_finite() && *v >= 0.0)
+    };
+
+    header("retry-after-ms")
+        .map(|ms| Duration::from_secs_f64(ms / 1000.0))
+        .or_else(|| header("retry-after").map(Duration::from_secs_f64))
+}
+
+// Turns an error status of the response into a `HttpError`, with the message of the body.
+pub(crate) async fn error_for_status(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let headers = response.headers().clone();
+    let body = response.text().await.unwrap_or_default();
+    // The APIs answer {"error": "message"} or {"error": {"message": "message"}}.
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|v| {
+            let error = v.get("error")?;
+            error
+                .as_str()
+                .or_else(|| error.get("message")?.as_str())
+                .map(|x| x.to_string())
+        })
+        .unwrap_or(body);
+    Err(HttpError::create(status.as_u16(), &message)
+        .with_headers(&headers)
+        .into())
+}
@@ -12,17 +12,30 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::pin::Pin;
+
 use anyhow::Result;
+use futures::Stream;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::ChatMessage;
+use crate::GenerateOptions;
+use crate::Tool;
+use crate::ToolCall;
 
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingResult {
     // Usage
     pub prompt_tokens: u32,
     pub total_tokens: u32,
 
     pub embeddings: Vec<Vec<f32>>,
+    // The name of the LLM which answered.
+    pub provider: String,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct GenerateResult {
     // Usage
     pub prompt_tokens: u32,
@@ -30,18 +43,94 @@ pub struct GenerateResult {
     pub total_tokens: u32,
 
     pub generation: String,
+    // All the generations when more than one is asked by `GenerateOptions::n`.
+    pub generations: Vec<String>,
+    // The tools the model asked to call, empty if it answered directly.
+    pub tool_calls: Vec<ToolCall>,
+    // The name of the LLM which answered.
+    pub provider: String,
 }
 
-pub struct ChatResult {
-    pub role: String,
-    pub content: String,
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenerateChunk {
+    // A piece of the generation, in the order the model produced it.
+    Delta(String),
+    // Usage of the whole generation, always the last item of the stream.
+    Usage {
+        prompt_tokens: u32,
+        completion_tokens: u32,
+        total_tokens: u32,
+    },
 }
 
+pub type GenerateStream = Pin<Box<dyn Stream<Item = Result<GenerateChunk>> + Send>>;
+
 #[async_trait::async_trait]
 pub trait LLM: Send + Sync {
+    // The name of the LLM, the type name by default.
+    fn name(&self) -> String {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name).to_string()
+    }
+
+    // The models answering when `GenerateOptions::model` is unset, empty if the LLM has none.
+    fn generate_model(&self) -> String {
+        "".to_string()
+    }
+
+    fn embedding_model(&self) -> String {
+        "".to_string()
+    }
+
     async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult>;
-    async fn generate(&self, input: &str) -> Result<GenerateResult>;
-    async fn chat(&self, _input: Vec<String>) -> Result<Vec<ChatResult>> {
-        unimplemented!("")
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult>;
+
+    async fn chat_with_tools(
+        &self,
+        _messages: Vec<ChatMessage>,
+        _tools: Vec<Tool>,
+        _options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        anyhow::bail!("tool calling is not supported by this LLM")
+    }
+
+    async fn generate(&self, input: &str) -> Result<GenerateResult> {
+        self.generate_with_options(input, &GenerateOptions::default())
+            .await
+    }
+
+    async fn generate_with_options(
+        &self,
+        input: &str,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        self.chat(vec![ChatMessage::user(input)], options).await
+    }
+
+    async fn generate_stream(&self, input: &str) -> Result<GenerateStream> {
+        self.chat_stream(vec![ChatMessage::user(input)], &GenerateOptions::default())
+            .await
+    }
+
+    // Backends without a streaming endpoint yield the whole generation as a single delta.
+    async fn chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateStream> {
+        let result = self.chat(messages, options).await?;
+        let chunks = vec![
+            Ok(GenerateChunk::Delta(result.generation)),
+            Ok(GenerateChunk::Usage {
+                prompt_tokens: result.prompt_tokens,
+                completion_tokens: result.completion_tokens,
+                total_tokens: result.total_tokens,
+            }),
+        ];
+        Ok(Box::pin(futures::stream::iter(chunks)))
     }
 }
@@ -0,0 +1,180 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use parking_lot::RwLock;
+
+use crate::fake_embedding;
+use crate::ChatMessage;
+use crate::EmbeddingResult;
+use crate::GenerateChunk;
+use crate::GenerateOptions;
+use crate::GenerateResult;
+use crate::GenerateStream;
+use crate::Tokenizer;
+use crate::LLM;
+
+// A deterministic LLM for the tests, it records the requests it received and answers with,
+// in order: the scripted responses, the first rule matching the prompt, the default response.
+pub struct MockLLM {
+    responses: Mutex<VecDeque<String>>,
+    // (pattern, response), a rule matches when the prompt contains its pattern.
+    rules: RwLock<Vec<(String, String)>>,
+    // The prompt is echoed if unset.
+    default_response: RwLock<Option<String>>,
+    // The embeddings are `fake_embedding` of this dimension.
+    embedding_dimension: RwLock<usize>,
+
+    requests: Mutex<Vec<Vec<ChatMessage>>>,
+    embedding_inputs: Mutex<Vec<String>>,
+}
+
+impl MockLLM {
+    pub fn create() -> Arc<Self> {
+        Arc::new(MockLLM {
+            responses: Mutex::new(VecDeque::new()),
+            rules: RwLock::new(vec![]),
+            default_response: RwLock::new(None),
+            embedding_dimension: RwLock::new(16),
+            requests: Mutex::new(vec![]),
+            embedding_inputs: Mutex::new(vec![]),
+        })
+    }
+
+    pub fn with_responses(self: &Arc<Self>, responses: Vec<&str>) -> Arc<Self> {
+        self.responses
+            .lock()
+            .extend(responses.iter().map(|x| x.to_string()));
+        self.clone()
+    }
+
+    pub fn with_rule(self: &Arc<Self>, pattern: &str, response: &str) -> Arc<Self> {
+        self.rules
+            .write()
+            .push((pattern.to_string(), response.to_string()));
+        self.clone()
+    }
+
+    pub fn with_default_response(self: &Arc<Self>, response: &str) -> Arc<Self> {
+        *self.default_response.write() = Some(response.to_string());
+        self.clone()
+    }
+
+    pub fn with_embedding_dimension(self: &Arc<Self>, dimension: usize) -> Arc<Self> {
+        *self.embedding_dimension.write() = dimension;
+        self.clone()
+    }
+
+    // The messages of every chat request, in order.
+    pub fn requests(&self) -> Vec<Vec<ChatMessage>> {
+        self.requests.lock().clone()
+    }
+
+    // The flattened prompt of every chat request, in order.
+    pub fn prompts(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .iter()
+            .map(|messages| ChatMessage::flatten(messages))
+            .collect()
+    }
+
+    pub fn embedding_inputs(&self) -> Vec<String> {
+        self.embedding_inputs.lock().clone()
+    }
+
+    fn respond(&self, prompt: &str) -> String {
+        if let Some(response) = self.responses.lock().pop_front() {
+            return response;
+        }
+
+        let rules = self.rules.read();
+        if let Some((_, response)) = rules.iter().find(|(pattern, _)| prompt.contains(pattern)) {
+            return response.clone();
+        }
+
+        self.default_response
+            .read()
+            .clone()
+            .unwrap_or_else(|| prompt.to_string())
+    }
+}
+
+#[async_trait::async_trait]
+impl LLM for MockLLM {
+    async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+        let dimension = *self.embedding_dimension.read();
+        let tokenizer = Tokenizer::default();
+        let prompt_tokens = inputs.iter().map(|x| tokenizer.count(x)).sum::<usize>() as u32;
+        let embeddings = inputs
+            .iter()
+            .map(|x| fake_embedding(x, dimension))
+            .collect();
+        self.embedding_inputs.lock().extend(inputs);
+
+        Ok(EmbeddingResult {
+            prompt_tokens,
+            total_tokens: prompt_tokens,
+            embeddings,
+            provider: self.name(),
+        })
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        _options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        let prompt = ChatMessage::flatten(&messages);
+        let generation = self.respond(&prompt);
+        self.requests.lock().push(messages);
+
+        let tokenizer = Tokenizer::default();
+        let prompt_tokens = tokenizer.count(&prompt) as u32;
+        let completion_tokens = tokenizer.count(&generation) as u32;
+        Ok(GenerateResult {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            generations: vec![generation.clone()],
+            generation,
+            tool_calls: vec![],
+            provider: self.name(),
+        })
+    }
+
+    // The generation is streamed word by word.
+    async fn chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateStream> {
+        let result = self.chat(messages, options).await?;
+        let mut chunks = result
+            .generation
+            .split_inclusive(' ')
+            .map(|x| Ok(GenerateChunk::Delta(x.to_string())))
+            .collect::<Vec<_>>();
+        chunks.push(Ok(GenerateChunk::Usage {
+            prompt_tokens: result.prompt_tokens,
+            completion_tokens: result.completion_tokens,
+            total_tokens: result.total_tokens,
+        }));
+        Ok(Box::pin(futures::stream::iter(chunks)))
+    }
+}
@@ -12,16 +12,55 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod anthropic;
 mod azure_openai;
+mod cached_llm;
+mod chat_message;
 mod databend;
+mod fallback_llm;
+mod generate_options;
+mod http_error;
 mod llm;
+mod mock;
+mod ollama;
 mod openai;
+mod openai_compatible;
+mod retrying_llm;
+mod tool;
+mod usage_tracker;
 
+pub(crate) use anthropic::messages_stream;
+pub use anthropic::Anthropic;
+pub use anthropic::AnthropicModel;
 pub use azure_openai::AzureOpenAI;
+pub use cached_llm::CachedLLM;
+pub use chat_message::ChatMessage;
+pub use chat_message::ChatRole;
 pub use databend::DatabendLLM;
+pub use fallback_llm::FallbackLLM;
+pub use generate_options::GenerateOptions;
+pub(crate) use http_error::error_for_status;
+pub use http_error::HttpError;
 pub use llm::*;
+pub use mock::MockLLM;
+pub use ollama::Ollama;
+pub(crate) use openai::chat_completion_functions;
+pub(crate) use openai::chat_completion_messages;
+pub(crate) use openai::chat_completion_options;
+pub(crate) use openai::chat_completion_prompt_tokens;
+pub(crate) use openai::chat_completion_stream;
+pub(crate) use openai::chat_completion_tool_calls;
+pub(crate) use openai::seeded_chat_completion;
+pub(crate) use openai::seeded_chat_completion_stream;
 pub use openai::OpenAI;
 pub use openai::OpenAIBuilder;
 pub use openai::OpenAIBuilderError;
 pub use openai::OpenAIEmbeddingModel;
 pub use openai::OpenAIGenerateModel;
+pub use openai_compatible::OpenAICompatible;
+pub use retrying_llm::RetryingLLM;
+pub use tool::Tool;
+pub use tool::ToolCall;
+pub use usage_tracker::Usage;
+pub use usage_tracker::UsageKind;
+pub use usage_tracker::UsageTracker;
@@ -0,0 +1,249 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use log::warn;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::error_for_status;
+use crate::ChatMessage;
+use crate::ChatRole;
+use crate::EmbeddingResult;
+use crate::GenerateOptions;
+use crate::GenerateResult;
+use crate::UsageTracker;
+use crate::LLM;
+
+#[derive(Serialize)]
+struct OllamaMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Serialize, Default)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u16>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+}
+
+#[derive(Serialize)]
+struct OllamaChatRequest {
+    model: String,
+    messages: Vec<OllamaMessage>,
+    stream: bool,
+    options: OllamaOptions,
+}
+
+#[derive(Deserialize)]
+struct OllamaChatResponseMessage {
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct OllamaChatResponse {
+    message: OllamaChatResponseMessage,
+    #[serde(default)]
+    prompt_eval_count: u32,
+    #[serde(default)]
+    eval_count: u32,
+}
+
+#[derive(Serialize)]
+struct OllamaEmbedRequest {
+    model: String,
+    input: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct OllamaEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+    #[serde(default)]
+    prompt_eval_count: u32,
+}
+
+// A model served by Ollama, https://github.com/ollama/ollama/blob/main/docs/api.md
+pub struct Ollama {
+    // Such as http://localhost:11434
+    base_url: String,
+    http_client: reqwest::Client,
+
+    // The maximum number of tokens allowed for the generated answer, unbounded by default.
+    max_tokens: RwLock<Option<u16>>,
+    // The sampling temperature, the default of the model if unset.
+    temperature: RwLock<Option<f32>>,
+
+    embedding_model: RwLock<String>,
+    generate_model: RwLock<String>,
+
+    // Every call reports its token usage into the tracker, if any.
+    usage_tracker: RwLock<Option<Arc<UsageTracker>>>,
+}
+
+impl Ollama {
+    // The model is used for both the generation and the embedding.
+    pub fn create(base_url: &str, model: &str) -> Arc<Self> {
+        Arc::new(Ollama {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http_client: reqwest::Client::new(),
+            max_tokens: RwLock::new(None),
+            temperature: RwLock::new(None),
+            embedding_model: RwLock::new(model.to_string()),
+            generate_model: RwLock::new(model.to_string()),
+            usage_tracker: RwLock::new(None),
+        })
+    }
+
+    pub fn with_max_tokens(self: &Arc<Self>, max_tokens: u16) -> Arc<Self> {
+        *self.max_tokens.write() = Some(max_tokens);
+        self.clone()
+    }
+
+    pub fn with_temperature(self: &Arc<Self>, temperature: f32) -> Arc<Self> {
+        *self.temperature.write() = Some(temperature);
+        self.clone()
+    }
+
+    pub fn with_embedding_model(self: &Arc<Self>, model: &str) -> Arc<Self> {
+        *self.embedding_model.write() = model.to_string();
+        self.clone()
+    }
+
+    pub fn with_generate_model(self: &Arc<Self>, model: &str) -> Arc<Self> {
+        *self.generate_model.write() = model.to_string();
+        self.clone()
+    }
+
+    pub fn with_usage_tracker(self: &Arc<Self>, tracker: Arc<UsageTracker>) -> Arc<Self> {
+        *self.usage_tracker.write() = Some(tracker);
+        self.clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl LLM for Ollama {
+    fn generate_model(&self) -> String {
+        self.generate_model.read().clone()
+    }
+
+    fn embedding_model(&self) -> String {
+        self.embedding_model.read().clone()
+    }
+
+    async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+        let model = self.embedding_model.read().clone();
+        let request = OllamaEmbedRequest {
+            model: model.clone(),
+            input: inputs,
+        };
+
+        let response = self
+            .http_client
+            .post(format!("{}/api/embed", self.base_url))
+            .json(&request)
+            .send()
+            .await?;
+        let response: OllamaEmbedResponse = error_for_status(response).await?.json().await?;
+
+        let embedding_result = EmbeddingResult {
+            prompt_tokens: response.prompt_eval_count,
+            total_tokens: response.prompt_eval_count,
+            embeddings: response.embeddings,
+            provider: self.name(),
+        };
+        if let Some(tracker) = self.usage_tracker.read().as_ref() {
+            tracker.record_embedding(&model, &embedding_result);
+        }
+        Ok(embedding_result)
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        if options.n.is_some_and(|n| n > 1) {
+            warn!("Ollama generates one answer per request, n is ignored");
+        }
+
+        let model = options
+            .model
+            .clone()
+            .unwrap_or(self.generate_model.read().clone());
+        let request = OllamaChatRequest {
+            model: model.clone(),
+            messages: messages
+                .iter()
+                .map(|message| OllamaMessage {
+                    role: match message.role {
+                        ChatRole::System => "system",
+                        ChatRole::User => "user",
+                        ChatRole::Assistant => "assistant",
+                        ChatRole::Tool => "tool",
+                    }
+                    .to_string(),
+                    content: message.content.clone(),
+                })
+                .collect(),
+            stream: false,
+            options: OllamaOptions {
+                temperature: options.temperature.or(*self.temperature.read()),
+                top_p: options.top_p,
+                num_predict: options.max_tokens.or(*self.max_tokens.read()),
+                stop: options.stop.clone(),
+                seed: options.seed,
+                presence_penalty: options.presence_penalty,
+                frequency_penalty: options.frequency_penalty,
+            },
+        };
+
+        let response = self
+            .http_client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&request)
+            .send()
+            .await?;
+        let response: OllamaChatResponse = error_for_status(response).await?.json().await?;
+
+        let generation = response.message.content;
+        let generate_result = GenerateResult {
+            prompt_tokens: response.prompt_eval_count,
+            completion_tokens: response.eval_count,
+            total_tokens: response.prompt_eval_count + response.eval_count,
+            generations: vec![generation.clone()],
+            generation,
+            tool_calls: vec![],
+            provider: self.name(),
+        };
+        if let Some(tracker) = self.usage_tracker.read().as_ref() {
+            tracker.record_generation(&model, &generate_result);
+        }
+        Ok(generate_result)
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use async_openai::types::ChatCompletionFunctions;
+use async_openai::types::ChatCompletionRequestMessage;
+use async_openai::types::ChatCompletionResponseMessage;
+use async_openai::types::CreateChatCompletionRequestArgs;
+use async_openai::types::FunctionCall;
+use async_openai::types::Role;
+use async_openai::types::Stop;
+
+use crate::ChatMessage;
+use crate::ChatRole;
+use crate::GenerateOptions;
+use crate::Tokenizer;
+use crate::Tool;
+use crate::ToolCall;
+
+pub(crate) fn chat_completion_messages(
+    messages: &[ChatMessage],
+) -> Vec<ChatCompletionRequestMessage> {
+    messages
+        .iter()
+        .map(|message| {
+            let function_call = message.tool_call.as_ref().map(|tool_call| FunctionCall {
+                name: tool_call.name.clone(),
+                arguments: tool_call.arguments.to_string(),
+            });
+            // Content can be omitted only by the assistant messages with function calls.
+            let content = if function_call.is_some() && message.content.is_empty() {
+                None
+            } else {
+                Some(message.content.clone())
+            };
+
+            ChatCompletionRequestMessage {
+                role: match message.role {
+                    ChatRole::System => Role::System,
+                    ChatRole::User => Role::User,
+                    ChatRole::Assistant => Role::Assistant,
+                    ChatRole::Tool => Role::Function,
+                },
+                content,
+                name: message.name.clone(),
+                function_call,
+            }
+        })
+        .collect()
+}
+
+// Apply the options which have no client default, but the seed which the request has no field for.
+pub(crate) fn chat_completion_options(
+    request: &mut CreateChatCompletionRequestArgs,
+    options: &GenerateOptions,
+) {
+    if let Some(top_p) = options.top_p {
+        request.top_p(top_p);
+    }
+    if !options.stop.is_empty() {
+        request.stop(Stop::StringArray(options.stop.clone()));
+    }
+    if let Some(presence_penalty) = options.presence_penalty {
+        request.presence_penalty(presence_penalty);
+    }
+    if let Some(frequency_penalty) = options.frequency_penalty {
+        request.frequency_penalty(frequency_penalty);
+    }
+    if let Some(n) = options.n {
+        request.n(n);
+    }
+}
+
+pub(crate) fn chat_completion_functions(tools: &[Tool]) -> Vec<ChatCompletionFunctions> {
+    tools
+        .iter()
+        .map(|tool| ChatCompletionFunctions {
+            name: tool.name.clone(),
+            description: Some(tool.description.clone()),
+            parameters: Some(tool.parameters.clone()),
+        })
+        .collect()
+}
+
+pub(crate) fn chat_completion_tool_calls(
+    message: &ChatCompletionResponseMessage,
+) -> Result<Vec<ToolCall>> {
+    match &message.function_call {
+        Some(function_call) => Ok(vec![ToolCall::create(
+            &function_call.name,
+            &function_call.arguments,
+        )?]),
+        None => Ok(vec![]),
+    }
+}
+
+// Tokens of the messages as the chat completions count them: every message is wrapped
+// by a few special tokens, and the reply is primed with a few more.
+pub(crate) fn chat_completion_prompt_tokens(
+    tokenizer: Tokenizer,
+    messages: &[ChatMessage],
+) -> usize {
+    let mut tokens = 3;
+    for message in messages {
+        tokens += 4 + tokenizer.count(&message.content);
+        if let Some(name) = &message.name {
+            tokens += tokenizer.count(name);
+        }
+        if let Some(tool_call) = &message.tool_call {
+            tokens += tokenizer.count(&tool_call.name);
+            tokens += tokenizer.count(&tool_call.arguments.to_string());
+        }
+    }
+    tokens
+}
@@ -0,0 +1,82 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use async_openai::types::ChatCompletionResponseStream;
+use futures::StreamExt;
+
+use crate::GenerateChunk;
+use crate::GenerateStream;
+use crate::Tokenizer;
+
+struct ChatStreamState {
+    response: ChatCompletionResponseStream,
+    tokenizer: Tokenizer,
+    prompt_tokens: usize,
+    generation: String,
+    finished: bool,
+}
+
+// Turn the chat completions SSE stream into deltas, followed by a usage item.
+// The streaming endpoint doesn't report usage, so the tokens are counted locally.
+pub(crate) fn chat_completion_stream(
+    response: ChatCompletionResponseStream,
+    tokenizer: Tokenizer,
+    prompt_tokens: usize,
+) -> GenerateStream {
+    let state = ChatStreamState {
+        response,
+        tokenizer,
+        prompt_tokens,
+        generation: String::new(),
+        finished: false,
+    };
+
+    Box::pin(futures::stream::unfold(state, |mut state| async move {
+        if state.finished {
+            return None;
+        }
+
+        while let Some(response) = state.response.next().await {
+            match response {
+                Ok(response) => {
+                    let delta = response
+                        .choices
+                        .iter()
+                        .find(|choice| choice.index == 0)
+                        .and_then(|choice| choice.delta.content.clone())
+                        .unwrap_or_default();
+                    if delta.is_empty() {
+                        continue;
+                    }
+                    state.generation.push_str(&delta);
+                    return Some((Ok(GenerateChunk::Delta(delta)), state));
+                }
+                Err(e) => {
+                    state.finished = true;
+                    return Some((Err(e.into()), state));
+                }
+            }
+        }
+
+        state.finished = true;
+        let prompt_tokens = state.prompt_tokens as u32;
+        let completion_tokens = state.tokenizer.count(&state.generation) as u32;
+        let chunk = GenerateChunk::Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        };
+        Some((Ok(chunk), state))
+    }))
+}
@@ -12,11 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod chat_request;
+mod chat_stream;
 #[allow(clippy::module_inception)]
 mod openai;
+mod seeded_request;
 
+pub(crate) use chat_request::chat_completion_functions;
+pub(crate) use chat_request::chat_completion_messages;
+pub(crate) use chat_request::chat_completion_options;
+pub(crate) use chat_request::chat_completion_prompt_tokens;
+pub(crate) use chat_request::chat_completion_tool_calls;
+pub(crate) use chat_stream::chat_completion_stream;
 pub use openai::OpenAI;
 pub use openai::OpenAIBuilder;
 pub use openai::OpenAIBuilderError;
 pub use openai::OpenAIEmbeddingModel;
 pub use openai::OpenAIGenerateModel;
+pub(crate) use seeded_request::seeded_chat_completion;
+pub(crate) use seeded_request::seeded_chat_completion_stream;
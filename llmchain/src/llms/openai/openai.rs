@@ -12,39 +12,88 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::sync::Arc;
+use std::time::Duration;
+
 use anyhow::Result;
 use async_openai::config::OpenAIConfig;
-use async_openai::types::ChatCompletionRequestMessageArgs;
+use async_openai::types::CreateChatCompletionRequest;
 use async_openai::types::CreateChatCompletionRequestArgs;
 use async_openai::types::CreateEmbeddingRequestArgs;
-use async_openai::types::Role;
 use async_openai::Client;
+use backoff::ExponentialBackoff;
 use derive_builder::Builder;
 
+use crate::chat_completion_functions;
+use crate::chat_completion_messages;
+use crate::chat_completion_options;
+use crate::chat_completion_prompt_tokens;
+use crate::chat_completion_stream;
+use crate::chat_completion_tool_calls;
+use crate::completion_budget;
+use crate::seeded_chat_completion;
+use crate::seeded_chat_completion_stream;
+use crate::ChatMessage;
 use crate::EmbeddingResult;
+use crate::GenerateOptions;
 use crate::GenerateResult;
+use crate::GenerateStream;
+use crate::ModelInfo;
+use crate::ModelRegistry;
+use crate::Tool;
+use crate::UsageTracker;
 use crate::LLM;
 
 pub enum OpenAIEmbeddingModel {
     TextEmbeddingAda002,
+    TextEmbedding3Small,
+    TextEmbedding3Large,
 }
 
-impl ToString for OpenAIEmbeddingModel {
-    fn to_string(&self) -> String {
-        "text-embedding-ada-002".to_string()
+impl OpenAIEmbeddingModel {
+    pub fn info(&self) -> ModelInfo {
+        ModelRegistry::global().get_or_default(&self.to_string())
+    }
+}
+
+impl Display for OpenAIEmbeddingModel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpenAIEmbeddingModel::TextEmbeddingAda002 => write!(f, "text-embedding-ada-002"),
+            OpenAIEmbeddingModel::TextEmbedding3Small => write!(f, "text-embedding-3-small"),
+            OpenAIEmbeddingModel::TextEmbedding3Large => write!(f, "text-embedding-3-large"),
+        }
     }
 }
 
 pub enum OpenAIGenerateModel {
     Gpt35,
+    Gpt35Turbo16k,
     Gpt4,
+    Gpt432k,
+    Gpt4Turbo,
+    Gpt4o,
+    Gpt4oMini,
 }
 
-impl ToString for OpenAIGenerateModel {
-    fn to_string(&self) -> String {
+impl OpenAIGenerateModel {
+    pub fn info(&self) -> ModelInfo {
+        ModelRegistry::global().get_or_default(&self.to_string())
+    }
+}
+
+impl Display for OpenAIGenerateModel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            OpenAIGenerateModel::Gpt35 => "gpt-3.5-turbo".to_string(),
-            OpenAIGenerateModel::Gpt4 => "gpt-4".to_string(),
+            OpenAIGenerateModel::Gpt35 => write!(f, "gpt-3.5-turbo"),
+            OpenAIGenerateModel::Gpt35Turbo16k => write!(f, "gpt-3.5-turbo-16k"),
+            OpenAIGenerateModel::Gpt4 => write!(f, "gpt-4"),
+            OpenAIGenerateModel::Gpt432k => write!(f, "gpt-4-32k"),
+            OpenAIGenerateModel::Gpt4Turbo => write!(f, "gpt-4-turbo"),
+            OpenAIGenerateModel::Gpt4o => write!(f, "gpt-4o"),
+            OpenAIGenerateModel::Gpt4oMini => write!(f, "gpt-4o-mini"),
         }
     }
 }
@@ -53,22 +102,46 @@ impl ToString for OpenAIGenerateModel {
 #[builder(name = "OpenAIBuilder")]
 #[builder(derive(Debug))]
 pub struct OpenAI {
+    #[builder(default = "\"https://api.openai.com/v1\".to_string()")]
     api_base: String,
     api_key: String,
+    #[builder(default)]
     org_id: Option<String>,
 
     // The maximum number of tokens allowed for the generated answer.
-    // By default, the number of tokens the model can return will be (4095 - prompt tokens).
-    max_tokens: u16,
+    // By default, the answer can use the context window left by the prompt, up to the output cap of the model.
+    #[builder(default, setter(strip_option))]
+    max_tokens: Option<u16>,
+
+    // The tokens kept for the answer, a prompt which leaves less of the context window is rejected.
+    #[builder(default = "256")]
+    reserved_completion_tokens: usize,
+
+    // The context window of the models, the registry one if unset, for the models it doesn't know.
+    #[builder(default, setter(strip_option))]
+    context_length: Option<usize>,
 
     // What sampling temperature to use, between 0 and 2. Higher values like 0.8 will make the output more random, while lower values like 0.2 will make it more focused and deterministic.
     // We generally recommend altering this or top_p but not both.
+    #[builder(default = "1.0")]
     temperature: f32,
 
+    #[builder(default = "OpenAIEmbeddingModel::TextEmbeddingAda002.to_string()")]
     embedding_model: String,
+    #[builder(default = "OpenAIGenerateModel::Gpt35.to_string()")]
     generate_model: String,
 
+    #[builder(default)]
     http_client: reqwest::Client,
+
+    // The client retries the rate limited requests by itself,
+    // turn it off when a `RetryingLLM` owns the retries.
+    #[builder(default = "true")]
+    rate_limit_backoff: bool,
+
+    // Every call reports its token usage into the tracker, if any.
+    #[builder(default, setter(strip_option))]
+    usage_tracker: Option<Arc<UsageTracker>>,
 }
 
 impl OpenAI {
@@ -79,7 +152,7 @@ impl OpenAI {
             .unwrap()
     }
 
-    fn get_client(&self) -> Client<OpenAIConfig> {
+    fn config(&self) -> OpenAIConfig {
         let mut conf = OpenAIConfig::new()
             .with_api_key(&self.api_key)
             .with_api_base(&self.api_base);
@@ -87,49 +160,121 @@ impl OpenAI {
         if let Some(org_id) = &self.org_id {
             conf = conf.with_org_id(org_id);
         }
+        conf
+    }
 
-        Client::with_config(conf).with_http_client(self.http_client.clone())
+    fn get_client(&self) -> Client<OpenAIConfig> {
+        let client = Client::with_config(self.config()).with_http_client(self.http_client.clone());
+        if self.rate_limit_backoff {
+            client
+        } else {
+            client.with_backoff(ExponentialBackoff {
+                max_elapsed_time: Some(Duration::ZERO),
+                ..Default::default()
+            })
+        }
+    }
+
+    fn chat_request(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[Tool],
+        options: &GenerateOptions,
+    ) -> Result<CreateChatCompletionRequest> {
+        let model = options.model.clone().unwrap_or(self.generate_model.clone());
+        let mut model_info = ModelRegistry::global().get_or_default(&model);
+        if let Some(context_length) = self.context_length {
+            model_info.context_length = context_length;
+            model_info.max_output_tokens = model_info.max_output_tokens.min(context_length);
+        }
+        let budget = completion_budget(
+            chat_completion_prompt_tokens(model_info.tokenizer, messages),
+            model_info.context_length,
+            model_info.max_output_tokens,
+            self.reserved_completion_tokens,
+        )?;
+        let max_tokens = options
+            .max_tokens
+            .or(self.max_tokens)
+            .map_or(budget, |max_tokens| max_tokens.min(budget));
+
+        let mut request = CreateChatCompletionRequestArgs::default();
+        request
+            .max_tokens(max_tokens)
+            .model(model)
+            .temperature(options.temperature.unwrap_or(self.temperature))
+            .messages(chat_completion_messages(messages));
+        chat_completion_options(&mut request, options);
+        if !tools.is_empty() {
+            request.functions(chat_completion_functions(tools));
+        }
+        Ok(request.build()?)
     }
 }
 
 #[async_trait::async_trait]
 impl LLM for OpenAI {
+    fn generate_model(&self) -> String {
+        self.generate_model.clone()
+    }
+
+    fn embedding_model(&self) -> String {
+        self.embedding_model.clone()
+    }
+
     async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
         let request = CreateEmbeddingRequestArgs::default()
-            .model(&self.embedding_model.to_string())
+            .model(self.embedding_model.to_string())
             .input(inputs)
             .build()?;
 
         let client = self.get_client();
         let response = client.embeddings().create(request).await?;
-        let mut embeddings = Vec::with_capacity(response.data.len());
-        for embedding in &response.data {
-            embeddings.push(embedding.embedding.clone());
-        }
+        // In the order of the inputs, whatever the order of the answer.
+        let mut data = response.data;
+        data.sort_by_key(|x| x.index);
+        let embeddings = data.into_iter().map(|x| x.embedding).collect::<Vec<_>>();
 
         let embedding_result = EmbeddingResult {
             prompt_tokens: response.usage.prompt_tokens,
             total_tokens: response.usage.total_tokens,
             embeddings,
+            provider: self.name(),
         };
+        if let Some(tracker) = &self.usage_tracker {
+            tracker.record_embedding(&self.embedding_model, &embedding_result);
+        }
         Ok(embedding_result)
     }
 
-    async fn generate(&self, input: &str) -> Result<GenerateResult> {
-        let request = CreateChatCompletionRequestArgs::default()
-            .max_tokens(self.max_tokens - input.len() as u16)
-            .model(self.generate_model.to_string())
-            .temperature(self.temperature)
-            .messages([ChatCompletionRequestMessageArgs::default()
-                .role(Role::Assistant)
-                .content(input)
-                .build()?])
-            .build()?;
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        self.chat_with_tools(messages, vec![], options).await
+    }
 
-        let client = self.get_client();
-        let response = client.chat().create(request).await?;
+    async fn chat_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<Tool>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        let request = self.chat_request(&messages, &tools, options)?;
+        let model = request.model.clone();
 
-        let mut generate_result = GenerateResult::default();
+        let response = match options.seed {
+            Some(seed) => {
+                seeded_chat_completion(&self.config(), &self.http_client, &request, seed).await?
+            }
+            None => self.get_client().chat().create(request).await?,
+        };
+
+        let mut generate_result = GenerateResult {
+            provider: self.name(),
+            ..Default::default()
+        };
 
         // Usage.
         if let Some(usage) = response.usage {
@@ -140,8 +285,41 @@ impl LLM for OpenAI {
 
         if let Some(choice) = response.choices.first() {
             generate_result.generation = choice.message.content.clone().unwrap_or_default();
+            generate_result.tool_calls = chat_completion_tool_calls(&choice.message)?;
         }
+        generate_result.generations = response
+            .choices
+            .iter()
+            .map(|choice| choice.message.content.clone().unwrap_or_default())
+            .collect();
 
+        if let Some(tracker) = &self.usage_tracker {
+            tracker.record_generation(&model, &generate_result);
+        }
         Ok(generate_result)
     }
+
+    async fn chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateStream> {
+        let request = self.chat_request(&messages, &[], options)?;
+        let model = request.model.clone();
+        let tokenizer = ModelRegistry::global().get_or_default(&model).tokenizer;
+        let prompt_tokens = chat_completion_prompt_tokens(tokenizer, &messages);
+
+        let response = match options.seed {
+            Some(seed) => {
+                seeded_chat_completion_stream(&self.config(), &self.http_client, &request, seed)
+                    .await?
+            }
+            None => self.get_client().chat().create_stream(request).await?,
+        };
+        let stream = chat_completion_stream(response, tokenizer, prompt_tokens);
+        Ok(match &self.usage_tracker {
+            Some(tracker) => tracker.track_stream(&model, stream),
+            None => stream,
+        })
+    }
 }
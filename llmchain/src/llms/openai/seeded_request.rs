@@ -0,0 +1,128 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use async_openai::config::Config;
+use async_openai::error::OpenAIError;
+use async_openai::types::ChatCompletionResponseStream;
+use async_openai::types::CreateChatCompletionRequest;
+use async_openai::types::CreateChatCompletionResponse;
+use async_openai::types::CreateChatCompletionStreamResponse;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+
+use crate::error_for_status;
+
+// The async-openai chat request has no seed field, so a seeded request is posted by hand:
+// the JSON of the request with the seed added, to the url and with the headers of the client.
+// Its errors are a `HttpError`, the client backoff doesn't retry them.
+async fn post_seeded<C: Config>(
+    config: &C,
+    http_client: &reqwest::Client,
+    request: &CreateChatCompletionRequest,
+    seed: i64,
+) -> Result<reqwest::Response> {
+    let mut body = serde_json::to_value(request)?;
+    body["seed"] = seed.into();
+
+    let response = http_client
+        .post(config.url("/chat/completions"))
+        .query(&config.query())
+        .headers(config.headers())
+        .json(&body)
+        .send()
+        .await?;
+    error_for_status(response).await
+}
+
+pub(crate) async fn seeded_chat_completion<C: Config>(
+    config: &C,
+    http_client: &reqwest::Client,
+    request: &CreateChatCompletionRequest,
+    seed: i64,
+) -> Result<CreateChatCompletionResponse> {
+    let response = post_seeded(config, http_client, request, seed).await?;
+    Ok(response.json().await?)
+}
+
+struct SeededStreamState {
+    response: BoxStream<'static, reqwest::Result<Vec<u8>>>,
+    buffer: Vec<u8>,
+    finished: bool,
+}
+
+impl SeededStreamState {
+    // Pops the data of the next complete event of the buffer, the events are separated by a blank line.
+    fn next_data(&mut self) -> Option<String> {
+        while let Some(pos) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let event = self.buffer.drain(..pos + 2).collect::<Vec<_>>();
+            let data = String::from_utf8_lossy(&event)
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|line| line.trim_start().to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            if !data.is_empty() {
+                return Some(data);
+            }
+        }
+        None
+    }
+}
+
+// The SSE chunks of a seeded request, as the client `create_stream` returns them.
+pub(crate) async fn seeded_chat_completion_stream<C: Config>(
+    config: &C,
+    http_client: &reqwest::Client,
+    request: &CreateChatCompletionRequest,
+    seed: i64,
+) -> Result<ChatCompletionResponseStream> {
+    let mut request = request.clone();
+    request.stream = Some(true);
+    let response = post_seeded(config, http_client, &request, seed).await?;
+    let state = SeededStreamState {
+        response: response
+            .bytes_stream()
+            .map(|bytes| bytes.map(|bytes| bytes.to_vec()))
+            .boxed(),
+        buffer: vec![],
+        finished: false,
+    };
+
+    Ok(Box::pin(futures::stream::unfold(
+        state,
+        |mut state| async move {
+            while !state.finished {
+                if let Some(data) = state.next_data() {
+                    if data == "[DONE]" {
+                        return None;
+                    }
+                    let chunk = serde_json::from_str::<CreateChatCompletionStreamResponse>(&data)
+                        .map_err(OpenAIError::JSONDeserialize);
+                    return Some((chunk, state));
+                }
+
+                match state.response.next().await {
+                    Some(Ok(bytes)) => state.buffer.extend(bytes.iter().filter(|b| **b != b'\r')),
+                    Some(Err(e)) => {
+                        state.finished = true;
+                        return Some((Err(OpenAIError::Reqwest(e)), state));
+                    }
+                    None => state.finished = true,
+                }
+            }
+            None
+        },
+    )))
+}
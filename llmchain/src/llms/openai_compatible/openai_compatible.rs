@@ -0,0 +1,205 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use parking_lot::RwLock;
+
+use crate::ChatMessage;
+use crate::EmbeddingResult;
+use crate::GenerateOptions;
+use crate::GenerateResult;
+use crate::GenerateStream;
+use crate::OpenAI;
+use crate::OpenAIBuilder;
+use crate::Tool;
+use crate::UsageTracker;
+use crate::LLM;
+
+// A server speaking the OpenAI chat completions and embeddings API,
+// such as the llama.cpp server, vLLM or LM Studio.
+// It's the `OpenAI` client pointed at the server, with the models of the server.
+pub struct OpenAICompatible {
+    // Such as http://localhost:8000/v1
+    base_url: String,
+    api_key: RwLock<Option<String>>,
+
+    // The maximum number of tokens allowed for the generated answer,
+    // all the context window left by the prompt if unset.
+    max_tokens: RwLock<Option<u16>>,
+    // The tokens kept for the answer, the `OpenAI` default if unset.
+    reserved_completion_tokens: RwLock<Option<usize>>,
+    // The context window of the model, the registry one if unset,
+    // the local models it doesn't know are assumed to be 4K.
+    context_length: RwLock<Option<usize>>,
+    // The sampling temperature, the `OpenAI` default if unset.
+    temperature: RwLock<Option<f32>>,
+
+    embedding_model: RwLock<String>,
+    generate_model: RwLock<String>,
+
+    // Every call reports its token usage into the tracker, if any.
+    usage_tracker: RwLock<Option<Arc<UsageTracker>>>,
+
+    // Shared by the clients built, to keep the connections to the server.
+    http_client: reqwest::Client,
+    // The client of the settings, built at the first call and again after a setter.
+    openai: RwLock<Option<Arc<OpenAI>>>,
+}
+
+impl OpenAICompatible {
+    // The model is used for both the generation and the embedding.
+    pub fn create(base_url: &str, model: &str) -> Arc<Self> {
+        Arc::new(OpenAICompatible {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: RwLock::new(None),
+            max_tokens: RwLock::new(None),
+            reserved_completion_tokens: RwLock::new(None),
+            context_length: RwLock::new(None),
+            temperature: RwLock::new(None),
+            embedding_model: RwLock::new(model.to_string()),
+            generate_model: RwLock::new(model.to_string()),
+            usage_tracker: RwLock::new(None),
+            http_client: reqwest::Client::new(),
+            openai: RwLock::new(None),
+        })
+    }
+
+    pub fn with_api_key(self: &Arc<Self>, api_key: &str) -> Arc<Self> {
+        *self.api_key.write() = Some(api_key.to_string());
+        self.reset()
+    }
+
+    pub fn with_max_tokens(self: &Arc<Self>, max_tokens: u16) -> Arc<Self> {
+        *self.max_tokens.write() = Some(max_tokens);
+        self.reset()
+    }
+
+    pub fn with_reserved_completion_tokens(self: &Arc<Self>, tokens: usize) -> Arc<Self> {
+        *self.reserved_completion_tokens.write() = Some(tokens);
+        self.reset()
+    }
+
+    pub fn with_context_length(self: &Arc<Self>, context_length: usize) -> Arc<Self> {
+        *self.context_length.write() = Some(context_length);
+        self.reset()
+    }
+
+    pub fn with_temperature(self: &Arc<Self>, temperature: f32) -> Arc<Self> {
+        *self.temperature.write() = Some(temperature);
+        self.reset()
+    }
+
+    pub fn with_embedding_model(self: &Arc<Self>, model: &str) -> Arc<Self> {
+        *self.embedding_model.write() = model.to_string();
+        self.reset()
+    }
+
+    pub fn with_generate_model(self: &Arc<Self>, model: &str) -> Arc<Self> {
+        *self.generate_model.write() = model.to_string();
+        self.reset()
+    }
+
+    pub fn with_usage_tracker(self: &Arc<Self>, tracker: Arc<UsageTracker>) -> Arc<Self> {
+        *self.usage_tracker.write() = Some(tracker);
+        self.reset()
+    }
+
+    // Drops the client built, the next call builds one with the new settings.
+    fn reset(self: &Arc<Self>) -> Arc<Self> {
+        *self.openai.write() = None;
+        self.clone()
+    }
+
+    // The servers without authentication ignore the empty key.
+    fn openai(&self) -> Result<Arc<OpenAI>> {
+        if let Some(openai) = self.openai.read().as_ref() {
+            return Ok(openai.clone());
+        }
+
+        let mut builder = OpenAIBuilder::default();
+        builder
+            .api_base(self.base_url.clone())
+            .api_key(self.api_key.read().clone().unwrap_or_default())
+            .embedding_model(self.embedding_model.read().clone())
+            .generate_model(self.generate_model.read().clone())
+            .http_client(self.http_client.clone());
+        if let Some(max_tokens) = *self.max_tokens.read() {
+            builder.max_tokens(max_tokens);
+        }
+        if let Some(tokens) = *self.reserved_completion_tokens.read() {
+            builder.reserved_completion_tokens(tokens);
+        }
+        if let Some(context_length) = *self.context_length.read() {
+            builder.context_length(context_length);
+        }
+        if let Some(temperature) = *self.temperature.read() {
+            builder.temperature(temperature);
+        }
+        if let Some(tracker) = self.usage_tracker.read().as_ref() {
+            builder.usage_tracker(tracker.clone());
+        }
+        let openai = Arc::new(builder.build()?);
+        *self.openai.write() = Some(openai.clone());
+        Ok(openai)
+    }
+}
+
+#[async_trait::async_trait]
+impl LLM for OpenAICompatible {
+    fn generate_model(&self) -> String {
+        self.generate_model.read().clone()
+    }
+
+    fn embedding_model(&self) -> String {
+        self.embedding_model.read().clone()
+    }
+
+    async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+        let mut result = self.openai()?.embedding(inputs).await?;
+        result.provider = self.name();
+        Ok(result)
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        self.chat_with_tools(messages, vec![], options).await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<Tool>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        let mut result = self
+            .openai()?
+            .chat_with_tools(messages, tools, options)
+            .await?;
+        result.provider = self.name();
+        Ok(result)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateStream> {
+        self.openai()?.chat_stream(messages, options).await
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_openai::error::OpenAIError;
+use futures::StreamExt;
+use log::warn;
+use parking_lot::Mutex;
+use parking_lot::RwLock;
+use rand::Rng;
+use tokio::time::Instant;
+
+use crate::ChatMessage;
+use crate::EmbeddingResult;
+use crate::GenerateOptions;
+use crate::GenerateResult;
+use crate::GenerateStream;
+use crate::HttpError;
+use crate::Tokenizer;
+use crate::Tool;
+use crate::LLM;
+
+// The window of the requests and tokens per minute limits.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+// Wraps any LLM, retries the transient errors (rate limits, server errors, timeouts)
+// with exponential backoff and jitter, and keeps the calls under the rate limits.
+//
+// The OpenAI client retries the rate limited requests by itself and drops the `Retry-After` header,
+// turn it off with `rate_limit_backoff(false)` to let this wrapper own the retries.
+pub struct RetryingLLM {
+    llm: Arc<dyn LLM>,
+
+    // The retries after the first attempt.
+    max_retries: RwLock<usize>,
+    // The delay before the first retry, doubled at every retry up to the max backoff.
+    initial_backoff: RwLock<Duration>,
+    max_backoff: RwLock<Duration>,
+
+    requests_per_minute: RwLock<Option<usize>>,
+    tokens_per_minute: RwLock<Option<usize>>,
+    // The requests and tokens sent during the last window.
+    window: Mutex<VecDeque<(Instant, usize, usize)>>,
+}
+
+impl RetryingLLM {
+    pub fn create(llm: Arc<dyn LLM>) -> Arc<Self> {
+        Arc::new(RetryingLLM {
+            llm,
+            max_retries: RwLock::new(5),
+            initial_backoff: RwLock::new(Duration::from_secs(1)),
+            max_backoff: RwLock::new(Duration::from_secs(60)),
+            requests_per_minute: RwLock::new(None),
+            tokens_per_minute: RwLock::new(None),
+            window: Mutex::new(VecDeque::new()),
+        })
+    }
+
+    pub fn with_max_retries(self: &Arc<Self>, max_retries: usize) -> Arc<Self> {
+        *self.max_retries.write() = max_retries;
+        self.clone()
+    }
+
+    pub fn with_initial_backoff(self: &Arc<Self>, backoff: Duration) -> Arc<Self> {
+        *self.initial_backoff.write() = backoff;
+        self.clone()
+    }
+
+    pub fn with_max_backoff(self: &Arc<Self>, backoff: Duration) -> Arc<Self> {
+        *self.max_backoff.write() = backoff;
+        self.clone()
+    }
+
+    pub fn with_requests_per_minute(self: &Arc<Self>, requests: usize) -> Arc<Self> {
+        *self.requests_per_minute.write() = Some(requests.max(1));
+        self.clone()
+    }
+
+    pub fn with_tokens_per_minute(self: &Arc<Self>, tokens: usize) -> Arc<Self> {
+        *self.tokens_per_minute.write() = Some(tokens.max(1));
+        self.clone()
+    }
+
+    fn has_rate_limits(&self) -> bool {
+        self.requests_per_minute.read().is_some() || self.tokens_per_minute.read().is_some()
+    }
+
+    // Waits until one more request of `tokens` fits in the rate limits.
+    async fn acquire(&self, tokens: usize) {
+        if !self.has_rate_limits() {
+            return;
+        }
+
+        loop {
+            let wait = {
+                let now = Instant::now();
+                let mut window = self.window.lock();
+                while let Some((at, _, _)) = window.front() {
+                    if now.duration_since(*at) < RATE_LIMIT_WINDOW {
+                        break;
+                    }
+                    window.pop_front();
+                }
+
+                let requests = window
+                    .iter()
+                    .map(|(_, requests, _)| requests)
+                    .sum::<usize>();
+                let used_tokens = window.iter().map(|(_, _, tokens)| tokens).sum::<usize>();
+                let requests_fit = self
+                    .requests_per_minute
+                    .read()
+                    .is_none_or(|limit| requests < limit);
+                // A request larger than the limit is let through once the window is empty.
+                let tokens_fit = self
+                    .tokens_per_minute
+                    .read()
+                    .is_none_or(|limit| used_tokens == 0 || used_tokens + tokens <= limit);
+                if requests_fit && tokens_fit {
+                    window.push_back((now, 1, tokens));
+                    return;
+                }
+
+                match window.front() {
+                    Some((at, _, _)) => RATE_LIMIT_WINDOW - now.duration_since(*at),
+                    None => return,
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // Accounts the tokens used beyond the estimate of the request.
+    fn record_tokens(&self, estimated: usize, actual: usize) {
+        if actual > estimated && self.has_rate_limits() {
+            self.window
+                .lock()
+                .push_back((Instant::now(), 0, actual - estimated));
+        }
+    }
+
+    fn backoff(&self, attempt: usize) -> Duration {
+        let max_backoff = *self.max_backoff.read();
+        let backoff = self
+            .initial_backoff
+            .read()
+            .saturating_mul(1 << attempt.min(16))
+            .min(max_backoff);
+        // Jitter, so that the concurrent callers don't retry all at once.
+        rand::thread_rng().gen_range(backoff / 2..=backoff)
+    }
+
+    async fn retry<T, F, Fut>(&self, tokens: usize, call: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            self.acquire(tokens).await;
+            let err = match call().await {
+                Ok(v) => return Ok(v),
+                Err(err) => err,
+            };
+
+            let max_retries = *self.max_retries.read();
+            if attempt >= max_retries || !is_transient(&err) {
+                return Err(err);
+            }
+
+            let delay = retry_after(&err).unwrap_or_else(|| self.backoff(attempt));
+            attempt += 1;
+            warn!(
+                "LLM call failed, retry {}/{} in {:?}: {}",
+                attempt, max_retries, delay, err
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl LLM for RetryingLLM {
+    fn name(&self) -> String {
+        self.llm.name()
+    }
+
+    fn generate_model(&self) -> String {
+        self.llm.generate_model()
+    }
+
+    fn embedding_model(&self) -> String {
+        self.llm.embedding_model()
+    }
+
+    async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+        let tokenizer = Tokenizer::default();
+        let tokens = inputs.iter().map(|x| tokenizer.count(x)).sum();
+
+        let inputs = &inputs;
+        let result = self
+            .retry(tokens, || self.llm.embedding(inputs.clone()))
+            .await?;
+        self.record_tokens(tokens, result.total_tokens as usize);
+        Ok(result)
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        let tokens = Tokenizer::default().count(&ChatMessage::flatten(&messages));
+
+        let messages = &messages;
+        let result = self
+            .retry(tokens, || self.llm.chat(messages.clone(), options))
+            .await?;
+        self.record_tokens(tokens, result.total_tokens as usize);
+        Ok(result)
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: Vec<ChatMessage>,
+        tools: Vec<Tool>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        let tokens = Tokenizer::default().count(&ChatMessage::flatten(&messages));
+
+        let messages = &messages;
+        let tools = &tools;
+        let result = self
+            .retry(tokens, || {
+                self.llm
+                    .chat_with_tools(messages.clone(), tools.clone(), options)
+            })
+            .await?;
+        self.record_tokens(tokens, result.total_tokens as usize);
+        Ok(result)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateStream> {
+        let tokens = Tokenizer::default().count(&ChatMessage::flatten(&messages));
+
+        let messages = &messages;
+        self.retry(tokens, || async move {
+            let mut stream = self.llm.chat_stream(messages.clone(), options).await?;
+            // The errors of the request come with the first item, once the stream is started it's not retried.
+            match stream.next().await {
+                Some(Ok(chunk)) => {
+                    let first = futures::stream::once(async { Ok(chunk) });
+                    Ok(Box::pin(first.chain(stream)) as GenerateStream)
+                }
+                Some(Err(err)) => Err(err),
+                None => Ok(stream),
+            }
+        })
+        .await
+    }
+}
+
+fn is_transient(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<HttpError>() {
+            e.is_transient()
+        } else if let Some(e) = cause.downcast_ref::<OpenAIError>() {
+            is_openai_transient(e)
+        } else if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            is_reqwest_transient(e)
+        } else {
+            false
+        }
+    })
+}
+
+fn is_openai_transient(err: &OpenAIError) -> bool {
+    match err {
+        OpenAIError::Reqwest(e) => is_reqwest_transient(e),
+        OpenAIError::ApiError(e) => {
+            let code = e.code.as_ref().and_then(|code| code.as_str());
+            matches!(
+                e.r#type.as_deref(),
+                Some("requests" | "tokens" | "rate_limit_exceeded" | "server_error")
+            ) || code == Some("rate_limit_exceeded")
+        }
+        // The stream reports the status of the request as "Invalid status code: 429 Too Many Requests".
+        OpenAIError::StreamError(message) => message
+            .strip_prefix("Invalid status code: ")
+            .and_then(|status| status.split_whitespace().next())
+            .and_then(|status| status.parse::<u16>().ok())
+            .is_some_and(|status| HttpError::create(status, "").is_transient()),
+        _ => false,
+    }
+}
+
+fn is_reqwest_transient(err: &reqwest::Error) -> bool {
+    err.is_timeout()
+        || err.is_connect()
+        || err
+            .status()
+            .is_some_and(|status| HttpError::create(status.as_u16(), "").is_transient())
+}
+
+fn retry_after(err: &anyhow::Error) -> Option<Duration> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<HttpError>())
+        .and_then(|e| e.retry_after)
+}
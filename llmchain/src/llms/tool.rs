@@ -0,0 +1,61 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+// A tool(function) the model may ask the caller to run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tool {
+    pub name: String,
+    pub description: String,
+    // The arguments the tool accepts, described as a JSON Schema object.
+    pub parameters: serde_json::Value,
+}
+
+impl Tool {
+    pub fn create(name: &str, description: &str, parameters: serde_json::Value) -> Self {
+        Tool {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters,
+        }
+    }
+}
+
+// A call of a tool generated by the model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+impl ToolCall {
+    pub fn create(name: &str, arguments: &str) -> Result<Self> {
+        // The model does not always generate valid JSON.
+        let arguments = serde_json::from_str(arguments).with_context(|| {
+            format!(
+                "tool call '{}' has invalid JSON arguments: {}",
+                name, arguments
+            )
+        })?;
+
+        Ok(ToolCall {
+            name: name.to_string(),
+            arguments,
+        })
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+use futures::StreamExt;
+use parking_lot::RwLock;
+
+use crate::EmbeddingResult;
+use crate::GenerateChunk;
+use crate::GenerateResult;
+use crate::GenerateStream;
+use crate::ModelRegistry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UsageKind {
+    Embedding,
+    Generation,
+}
+
+impl Display for UsageKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UsageKind::Embedding => write!(f, "embedding"),
+            UsageKind::Generation => write!(f, "generation"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Usage {
+    pub requests: usize,
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+    pub total_tokens: usize,
+    // Estimated dollars, from the prices of the model registry.
+    pub cost: f64,
+}
+
+impl Usage {
+    fn add(&mut self, other: &Usage) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        self.cost += other.cost;
+    }
+}
+
+// Aggregates the usage of every LLM call reporting into it, by model and kind.
+// It is shared with `Arc` so that all the LLMs of a pipeline report into one place.
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    usages: RwLock<HashMap<(String, UsageKind), Usage>>,
+}
+
+impl UsageTracker {
+    pub fn create() -> Arc<Self> {
+        Arc::new(UsageTracker::default())
+    }
+
+    pub fn record(
+        &self,
+        model: &str,
+        kind: UsageKind,
+        prompt_tokens: usize,
+        completion_tokens: usize,
+    ) {
+        let cost = ModelRegistry::global()
+            .get_or_default(model)
+            .cost(prompt_tokens, completion_tokens);
+        let usage = Usage {
+            requests: 1,
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            cost,
+        };
+
+        self.usages
+            .write()
+            .entry((model.to_string(), kind))
+            .or_default()
+            .add(&usage);
+    }
+
+    pub fn record_embedding(&self, model: &str, result: &EmbeddingResult) {
+        self.record(
+            model,
+            UsageKind::Embedding,
+            result.prompt_tokens as usize,
+            0,
+        );
+    }
+
+    pub fn record_generation(&self, model: &str, result: &GenerateResult) {
+        self.record(
+            model,
+            UsageKind::Generation,
+            result.prompt_tokens as usize,
+            result.completion_tokens as usize,
+        );
+    }
+
+    // Records the usage item of the stream when it goes through.
+    pub fn track_stream(self: &Arc<Self>, model: &str, stream: GenerateStream) -> GenerateStream {
+        let tracker = self.clone();
+        let model = model.to_string();
+        Box::pin(stream.inspect(move |chunk| {
+            if let Ok(GenerateChunk::Usage {
+                prompt_tokens,
+                completion_tokens,
+                ..
+            }) = chunk
+            {
+                tracker.record(
+                    &model,
+                    UsageKind::Generation,
+                    *prompt_tokens as usize,
+                    *completion_tokens as usize,
+                );
+            }
+        }))
+    }
+
+    pub fn usage(&self, model: &str, kind: UsageKind) -> Usage {
+        self.usages
+            .read()
+            .get(&(model.to_string(), kind))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // All the usages, sorted by model and kind.
+    pub fn usages(&self) -> Vec<(String, UsageKind, Usage)> {
+        let mut usages = self
+            .usages
+            .read()
+            .iter()
+            .map(|((model, kind), usage)| (model.clone(), *kind, usage.clone()))
+            .collect::<Vec<_>>();
+        usages.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+        usages
+    }
+
+    pub fn total(&self) -> Usage {
+        let mut total = Usage::default();
+        for usage in self.usages.read().values() {
+            total.add(usage);
+        }
+        total
+    }
+
+    pub fn total_by_kind(&self, kind: UsageKind) -> Usage {
+        let mut total = Usage::default();
+        for ((_, k), usage) in self.usages.read().iter() {
+            if *k == kind {
+                total.add(usage);
+            }
+        }
+        total
+    }
+
+    pub fn reset(&self) {
+        self.usages.write().clear();
+    }
+}
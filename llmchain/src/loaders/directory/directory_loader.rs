@@ -102,8 +102,9 @@ impl DocumentLoader for DirectoryLoader {
 
         let documents = Documents::create();
         for result in results {
-            let result = result.await?;
-            documents.extend(&result);
+            for document in result.await?.iter() {
+                documents.push(document.with_metadata("directory", path.as_str()?));
+            }
         }
 
         Ok(documents)
@@ -12,13 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::chat_tokens;
+use serde::Deserialize;
+use serde::Serialize;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+use crate::Metadata;
+use crate::MetadataValue;
+use crate::ModelRegistry;
+use crate::Tokenizer;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Document {
     pub path: String,
     pub content: String,
     pub content_md5: String,
+    #[serde(default)]
+    pub metadata: Metadata,
 }
 
 impl Document {
@@ -27,11 +35,36 @@ impl Document {
             path: path.to_string(),
             content: content.to_string(),
             content_md5: format!("{:x}", md5::compute(content)),
+            metadata: Metadata::new(),
         }
     }
 
+    pub fn with_metadata<V: Into<MetadataValue>>(mut self, key: &str, value: V) -> Self {
+        self.metadata.insert(key.to_string(), value.into());
+        self
+    }
+
+    // Replaces the content, keeping the path and the metadata, such as a chunk of the document.
+    pub fn with_content(&self, content: &str) -> Self {
+        Document {
+            path: self.path.clone(),
+            content: content.to_string(),
+            content_md5: format!("{:x}", md5::compute(content)),
+            metadata: self.metadata.clone(),
+        }
+    }
+
+    // Counted by r50k_base as always, `model_tokens` counts them for a model.
     pub fn tokens(&self) -> usize {
-        chat_tokens(&self.content).unwrap().len()
+        Tokenizer::R50kBase.count(&self.content)
+    }
+
+    // Tokens counted by the tokenizer of the model.
+    pub fn model_tokens(&self, model: &str) -> usize {
+        ModelRegistry::global()
+            .get_or_default(model)
+            .tokenizer
+            .count(&self.content)
     }
 
     pub fn size(&self) -> usize {
@@ -16,7 +16,7 @@ use anyhow::Result;
 
 use crate::Documents;
 
-pub trait DocumentSplitter {
+pub trait DocumentSplitter: Send + Sync {
     fn separators(&self) -> Vec<String>;
     fn split_documents(&self, documents: &Documents) -> Result<Documents>;
 }
@@ -76,7 +76,7 @@ impl FromIterator<Document> for Documents {
     }
 }
 
-impl<'a> IntoIterator for &'a Documents {
+impl IntoIterator for &Documents {
     type Item = Document;
     type IntoIter = DocumentsIter;
 
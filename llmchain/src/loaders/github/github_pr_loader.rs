@@ -53,10 +53,8 @@ impl DocumentLoader for GithubPRLoader {
                     .personal_token(self.person_token.clone())
                     .build()?,
             );
-            let diff = octocrab
-                .pulls(&self.owner, &self.repo)
-                .get_diff(id as u64)
-                .await;
+            let pulls = octocrab.pulls(&self.owner, &self.repo);
+            let diff = pulls.get_diff(id as u64).await;
 
             let path = format!(
                 "https://github.com/{}/{}/pull/{}",
@@ -68,7 +66,31 @@ impl DocumentLoader for GithubPRLoader {
             }
 
             let diff = diff?;
-            documents.push(Document::create(&path, &diff));
+            let mut document = Document::create(&path, &diff)
+                .with_metadata("source", path.as_str())
+                .with_metadata("owner", self.owner.as_str())
+                .with_metadata("repo", self.repo.as_str())
+                .with_metadata("pr_number", id as i64);
+
+            // The diff is enough to summarize, the details only help to cite the PR.
+            match pulls.get(id as u64).await {
+                Ok(pr) => {
+                    if let Some(title) = pr.title {
+                        document = document.with_metadata("title", title);
+                    }
+                    if let Some(user) = pr.user {
+                        document = document.with_metadata("author", user.login);
+                    }
+                    if let Some(created_at) = pr.created_at {
+                        document = document.with_metadata("created_at", created_at.to_rfc3339());
+                    }
+                    if let Some(updated_at) = pr.updated_at {
+                        document = document.with_metadata("updated_at", updated_at.to_rfc3339());
+                    }
+                }
+                Err(e) => info!("PR {} details not found, error:{:?}", path, e),
+            }
+            documents.push(document);
             info!(
                 "Loaded PR {}, diff_len {}, tokens {} in {:?}",
                 path,
@@ -17,7 +17,6 @@ use glob::Pattern;
 use log::info;
 use patch::Patch;
 
-use crate::Document;
 use crate::DocumentSplitter;
 use crate::Documents;
 
@@ -53,12 +52,13 @@ impl DocumentSplitter for GithubPRDiffSplitter {
     fn split_documents(&self, documents: &Documents) -> Result<Documents> {
         let diff_documents = Documents::create();
         let mut acc_patch_str = String::new();
-        let mut last_document_path = String::new();
+        // The files of the patches in acc_patch_str.
+        let mut acc_files: Vec<String> = vec![];
+        let mut last_document = None;
 
         for document in documents {
             let content = Box::leak(document.content.clone().into_boxed_str());
             let patches = Patch::from_multiple(content)?;
-            last_document_path = document.path.clone();
 
             for patch in patches {
                 let mut need_skip = false;
@@ -79,21 +79,33 @@ impl DocumentSplitter for GithubPRDiffSplitter {
                 if !need_skip {
                     let patch_str = format!("{}", patch);
 
+                    let file = patch.new.path.trim_start_matches("b/").to_string();
                     if acc_patch_str.len() + patch_str.len() <= self.splitter_chunk_size {
                         acc_patch_str.push('\n');
                         acc_patch_str.push_str(&patch_str);
+                        acc_files.push(file);
                     } else {
                         if !acc_patch_str.is_empty() {
-                            diff_documents.push(Document::create(&document.path, &acc_patch_str));
+                            diff_documents.push(
+                                document
+                                    .with_content(&acc_patch_str)
+                                    .with_metadata("files", acc_files.join(",")),
+                            );
                         }
                         acc_patch_str = patch_str;
+                        acc_files = vec![file];
                     }
                 }
             }
+            last_document = Some(document);
         }
 
-        if !acc_patch_str.is_empty() {
-            diff_documents.push(Document::create(&last_document_path, &acc_patch_str));
+        if let Some(document) = last_document.filter(|_| !acc_patch_str.is_empty()) {
+            diff_documents.push(
+                document
+                    .with_content(&acc_patch_str)
+                    .with_metadata("files", acc_files.join(",")),
+            );
         }
         info!(
             "Split {} documents into {} diff documents",
@@ -64,9 +64,15 @@ impl DocumentLoader for GithubRepoLoader {
         let result = result
             .iter()
             .map(|x| {
+                let file = x.path.replace(&local_path, "");
                 let mut x = x;
                 x.path = x.path.replace(&local_path, repo_url);
-                x
+                // The clone directory is gone once loaded.
+                x.metadata.remove("directory");
+                let source = x.path.clone();
+                x.with_metadata("source", source)
+                    .with_metadata("repository", repo_url)
+                    .with_metadata("file", file)
             })
             .collect::<Vec<_>>();
 
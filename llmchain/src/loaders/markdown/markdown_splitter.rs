@@ -57,6 +57,52 @@ impl DocumentSplitter for MarkdownSplitter {
         let text_splitter = TextSplitter::create()
             .with_chunk_size(self.splitter_chunk_size)
             .with_separators(self.separators());
-        text_splitter.split_documents(documents)
+
+        let result = Documents::create();
+        for document in documents {
+            let headings = heading_paths(&document.content);
+            let chunks = text_splitter.split_documents(&Documents::from(vec![document]))?;
+            for chunk in chunks.iter() {
+                let heading = chunk
+                    .metadata
+                    .get("line_start")
+                    .and_then(|x| x.as_i64())
+                    .and_then(|x| headings.get(x as usize - 1))
+                    .filter(|x| !x.is_empty())
+                    .cloned();
+                match heading {
+                    Some(heading) => result.push(chunk.with_metadata("heading", heading)),
+                    None => result.push(chunk),
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+// The path of the headings every line is under, such as "Install > From source".
+fn heading_paths(content: &str) -> Vec<String> {
+    let mut headings: Vec<(usize, String)> = vec![];
+    let mut in_code = false;
+    let mut paths = vec![];
+    for line in content.split('\n') {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+
+        let level = line.chars().take_while(|x| *x == '#').count();
+        if !in_code && (1..=6).contains(&level) && line[level..].starts_with(' ') {
+            headings.retain(|x| x.0 < level);
+            headings.push((level, line[level..].trim().to_string()));
+        }
+
+        paths.push(
+            headings
+                .iter()
+                .map(|x| x.1.as_str())
+                .collect::<Vec<_>>()
+                .join(" > "),
+        );
     }
+    paths
 }
@@ -16,6 +16,7 @@ mod directory;
 mod disk;
 mod document;
 mod document_loader;
+mod document_metadata;
 mod document_path;
 mod document_splitter;
 mod documents;
@@ -29,6 +30,8 @@ pub use disk::LocalDisk;
 pub use disk::RemoteDisk;
 pub use document::Document;
 pub use document_loader::DocumentLoader;
+pub use document_metadata::Metadata;
+pub use document_metadata::MetadataValue;
 pub use document_path::DocumentPath;
 pub use document_splitter::DocumentSplitter;
 pub use documents::Documents;
@@ -35,11 +35,44 @@ impl TextLoader {
 #[async_trait::async_trait]
 impl DocumentLoader for TextLoader {
     async fn load(&self, path: DocumentPath) -> Result<Documents> {
-        let bs = self.disk.get_operator()?.read(path.as_str()?).await?;
+        let path = path.as_str()?;
+        let op = self.disk.get_operator()?;
+        let bs = op.read(path).await?;
         let content = String::from_utf8_lossy(&bs).to_string();
-        let documents = Documents::create();
-        documents.push(Document::create(path.as_str()?, &content));
 
+        let mut document = Document::create(path, &content).with_metadata("source", path);
+        if let Some(language) = language(path) {
+            document = document.with_metadata("language", language);
+        }
+        if let Some(last_modified) = op.stat(path).await?.last_modified() {
+            document = document.with_metadata("last_modified", last_modified.to_rfc3339());
+        }
+
+        let documents = Documents::create();
+        documents.push(document);
         Ok(documents)
     }
 }
+
+// The language of the file, by its extension.
+fn language(path: &str) -> Option<&'static str> {
+    let extension = path.rsplit_once('.')?.1.to_lowercase();
+    let language = match extension.as_str() {
+        "c" | "h" => "c",
+        "cc" | "cpp" | "hpp" => "cpp",
+        "go" => "go",
+        "java" => "java",
+        "js" => "javascript",
+        "json" => "json",
+        "md" => "markdown",
+        "py" => "python",
+        "rs" => "rust",
+        "sql" => "sql",
+        "toml" => "toml",
+        "ts" => "typescript",
+        "txt" => "text",
+        "yaml" | "yml" => "yaml",
+        _ => return None,
+    };
+    Some(language)
+}
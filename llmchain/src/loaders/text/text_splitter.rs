@@ -15,7 +15,6 @@
 use anyhow::Result;
 use regex::Regex;
 
-use crate::Document;
 use crate::DocumentSplitter;
 use crate::Documents;
 
@@ -42,7 +41,8 @@ impl TextSplitter {
         self
     }
 
-    fn split_text(&self, text: &str) -> Result<Vec<String>> {
+    // The chunks with the range of lines they come from, 1-based and inclusive.
+    fn split_text(&self, text: &str) -> Result<Vec<(String, usize, usize)>> {
         // Splits.
         let separators = self.separators();
         let separator_pattern = separators
@@ -54,35 +54,52 @@ impl TextSplitter {
 
         let mut parts = Vec::new();
         let mut last_end = 0;
+        let mut line = 1;
+        let mut push_part = |start: usize, end: usize| {
+            line += text[last_end..start].matches('\n').count();
+            let part = &text[start..end];
+            let start_line = line;
+            let end_line = line + part.matches('\n').count();
+            parts.push((part.to_string(), start_line, end_line));
+            line = end_line;
+            last_end = end;
+        };
+        let mut start = 0;
         for cap in separator_regex.find_iter(text) {
-            let part = &text[last_end..cap.start()];
-            last_end = cap.end();
-            parts.push(part.to_string());
+            push_part(start, cap.start());
+            start = cap.end();
         }
-        parts.push(text[last_end..].to_string());
+        push_part(start, text.len());
 
         // Merge.
         let mut docs = Vec::new();
         let mut current_chunk = String::new();
-        for part in &parts {
+        let mut current_lines: Option<(usize, usize)> = None;
+        for (part, start_line, end_line) in &parts {
+            let lines = current_lines.map_or((*start_line, *end_line), |x| (x.0, *end_line));
             if current_chunk.len() > self.splitter_chunk_size {
-                docs.push(current_chunk.clone());
+                let (start_line, end_line) = current_lines.unwrap_or(lines);
+                docs.push((current_chunk.clone(), start_line, end_line));
                 current_chunk.clear();
+                current_lines = None;
             } else if current_chunk.len() + part.len() >= self.splitter_chunk_size {
                 current_chunk.push(' ');
                 current_chunk.push_str(part);
-                docs.push(current_chunk.clone());
+                docs.push((current_chunk.clone(), lines.0, lines.1));
                 current_chunk.clear();
+                current_lines = None;
             } else {
                 if !current_chunk.is_empty() {
                     current_chunk.push(' ');
                 }
                 current_chunk.push_str(part);
+                current_lines = Some(lines);
             }
         }
 
         if !current_chunk.is_empty() {
-            docs.push(current_chunk);
+            let (start_line, end_line) = current_lines.unwrap_or((line, line));
+            docs.push((current_chunk, start_line, end_line));
         }
 
         Ok(docs)
@@ -100,8 +117,14 @@ impl DocumentSplitter for TextSplitter {
         for document in documents {
             let chunks = self.split_text(&document.content)?;
 
-            for chunk in chunks {
-                result.push(Document::create(&document.path, &chunk))
+            for (i, (chunk, start_line, end_line)) in chunks.into_iter().enumerate() {
+                result.push(
+                    document
+                        .with_content(&chunk)
+                        .with_metadata("chunk", i)
+                        .with_metadata("line_start", start_line)
+                        .with_metadata("line_end", end_line),
+                )
             }
         }
         Ok(result)
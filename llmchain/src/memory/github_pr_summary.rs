@@ -19,7 +19,6 @@ use anyhow::Result;
 use log::info;
 use parking_lot::RwLock;
 
-use crate::chat_tokens;
 use crate::Documents;
 use crate::GithubPRSummaryPrompt;
 use crate::Prompt;
@@ -67,15 +66,13 @@ impl Summarize for GithubPRSummary {
             input_variables.insert("text", document.content.as_str());
             let prompt = prompt_template.format(input_variables)?;
 
-            let tokens = chat_tokens(&prompt)?;
-            *self.tokens.write() += tokens.len();
-
             let summary = self.llm.generate(&prompt).await?;
+            *self.tokens.write() += summary.total_tokens as usize;
             info!(
                 "summary [{}/{}, tokens {}]: \n{}",
                 i + 1,
                 documents.len(),
-                tokens.len(),
+                summary.total_tokens,
                 summary.generation
             );
             self.summaries.write().push(summary.generation);
@@ -96,12 +93,12 @@ impl Summarize for GithubPRSummary {
         let prompt_template = GithubPRSummaryPrompt::create();
         let prompt = prompt_template.format(input_variables)?;
 
-        let tokens = chat_tokens(&prompt)?;
-        *self.tokens.write() += tokens.len();
-        info!("prompt: tokens {}, result\n{}", tokens.len(), prompt);
-
         let summary = self.llm.generate(&prompt).await?;
-        info!("final summary: {}", summary.generation);
+        *self.tokens.write() += summary.total_tokens as usize;
+        info!(
+            "final summary: tokens {}, result\n{}",
+            summary.total_tokens, summary.generation
+        );
 
         Ok(summary.generation)
     }
@@ -15,16 +15,21 @@
 use std::sync::Arc;
 
 use anyhow::anyhow;
+use anyhow::bail;
 use anyhow::Result;
 use databend_driver::Client;
 use futures::StreamExt;
 use log::info;
 use uuid::Uuid;
 
-use crate::escape_sql_string;
+use crate::quote_sql_string;
+use crate::validate_sql_identifier;
 use crate::Document;
+use crate::DocumentKey;
 use crate::Documents;
 use crate::Embedding;
+use crate::Filter;
+use crate::Metadata;
 use crate::VectorStore;
 
 pub struct DatabendVectorStore {
@@ -60,23 +65,76 @@ impl DatabendVectorStore {
         self.min_similarity = similarity;
         self
     }
+
+    // The names are checked before every query, they are formatted into the SQL as they are.
+    fn table_name(&self) -> Result<String> {
+        validate_sql_identifier(&self.database)?;
+        validate_sql_identifier(&self.table)?;
+        Ok(format!("{}.{}", self.database, self.table))
+    }
+
+    // The DELETE doesn't report the rows it removed, they are counted first.
+    async fn delete_where(&self, condition: &str) -> Result<usize> {
+        let table = self.table_name()?;
+        let conn = self.client.get_conn().await?;
+
+        let count_sql = format!("SELECT count(*) FROM {} WHERE {}", table, condition);
+        let (count,): (u64,) = conn.query_row(&count_sql).await?.map_or(Ok((0,)), |row| {
+            row.try_into().map_err(|e: String| anyhow!(e))
+        })?;
+        if count == 0 {
+            return Ok(0);
+        }
+
+        let delete_sql = format!("DELETE FROM {} WHERE {}", table, condition);
+        conn.exec(&delete_sql).await?;
+        info!("deleted {} documents from {}", count, table);
+
+        Ok(count as usize)
+    }
+}
+
+fn sql_string_list(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|x| quote_sql_string(x))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 #[async_trait::async_trait]
 impl VectorStore for DatabendVectorStore {
     async fn init(&self) -> Result<()> {
+        let table = self.table_name()?;
         let conn = self.client.get_conn().await?;
 
         let database_create_sql = format!("CREATE DATABASE IF NOT EXISTS {}", self.database);
         conn.exec(&database_create_sql).await?;
 
         let table_create_sql = format!(
-            "CREATE TABLE IF NOT EXISTS {}.{} \
-            (uuid VARCHAR, path VARCHAR, content VARCHAR, content_md5 VARCHAR, embedding ARRAY(float32))",
-            self.database, self.table
+            "CREATE TABLE IF NOT EXISTS {} \
+            (uuid VARCHAR, path VARCHAR, content VARCHAR, content_md5 VARCHAR, embedding ARRAY(float32), metadata VARCHAR)",
+            table
         );
         conn.exec(&table_create_sql).await?;
 
+        // The tables created before the metadata get the column.
+        let column_count_sql = format!(
+            "SELECT count(*) FROM system.columns WHERE database = {} AND table = {} AND name = 'metadata'",
+            quote_sql_string(&self.database),
+            quote_sql_string(&self.table)
+        );
+        let (column_count,): (u64,) = conn
+            .query_row(&column_count_sql)
+            .await?
+            .map_or(Ok((0,)), |row| {
+                row.try_into().map_err(|e: String| anyhow!(e))
+            })?;
+        if column_count == 0 {
+            let column_add_sql = format!("ALTER TABLE {} ADD COLUMN metadata VARCHAR", table);
+            conn.exec(&column_add_sql).await?;
+        }
+
         Ok(())
     }
 
@@ -85,21 +143,30 @@ impl VectorStore for DatabendVectorStore {
             .map(|_| Uuid::new_v4().to_string())
             .collect::<Vec<_>>();
 
+        let table = self.table_name()?;
         let embeddings = self.embedding.embed_documents(inputs).await?;
+        if embeddings.len() != inputs.len() {
+            bail!(
+                "got {} embeddings for {} documents",
+                embeddings.len(),
+                inputs.len()
+            );
+        }
 
         let sql = format!(
-            "INSERT INTO {}.{} (uuid, path, content, content_md5, embedding) VALUES ",
-            self.database, self.table
+            "INSERT INTO {} (uuid, path, content, content_md5, embedding, metadata) VALUES ",
+            table
         );
         let mut val_vec = vec![];
         for (idx, doc) in inputs.iter().enumerate() {
             val_vec.push(format!(
-                "('{}', '{}', '{}', '{}', {:?})",
-                uuids[idx],
-                escape_sql_string(&doc.path),
-                escape_sql_string(&doc.content),
-                doc.content_md5,
-                embeddings[idx]
+                "({}, {}, {}, {}, {:?}, {})",
+                quote_sql_string(&uuids[idx]),
+                quote_sql_string(&doc.path),
+                quote_sql_string(&doc.content),
+                quote_sql_string(&doc.content_md5),
+                embeddings[idx],
+                quote_sql_string(&serde_json::to_string(&doc.metadata)?)
             ));
         }
         let values = val_vec.join(",").to_string();
@@ -111,19 +178,99 @@ impl VectorStore for DatabendVectorStore {
         Ok(uuids)
     }
 
-    async fn similarity_search(&self, query: &str, k: usize) -> Result<Vec<Document>> {
+    async fn delete(&self, uuids: &[String]) -> Result<usize> {
+        if uuids.is_empty() {
+            return Ok(0);
+        }
+        self.delete_where(&format!("uuid IN ({})", sql_string_list(uuids)))
+            .await
+    }
+
+    async fn delete_by_path(&self, path: &str) -> Result<usize> {
+        self.delete_where(&format!("path = {}", quote_sql_string(path)))
+            .await
+    }
+
+    async fn document_keys(&self, paths: &[String]) -> Result<Vec<DocumentKey>> {
+        if paths.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let sql = format!(
+            "SELECT uuid, path, content_md5, metadata FROM {} WHERE path IN ({})",
+            self.table_name()?,
+            sql_string_list(paths)
+        );
+        let mut keys = vec![];
+        let conn = self.client.get_conn().await?;
+        let mut rows = conn.query_iter(&sql).await?;
+        while let Some(row) = rows.next().await {
+            let (uuid, path, content_md5, metadata): (String, String, String, Option<String>) =
+                row?.try_into().map_err(|e: String| anyhow!(e))?;
+            keys.push(DocumentKey {
+                uuid,
+                path,
+                content_md5,
+                metadata: metadata
+                    .and_then(|x| serde_json::from_str(&x).ok())
+                    .unwrap_or_default(),
+            });
+        }
+
+        Ok(keys)
+    }
+
+    async fn update_metadata(&self, updates: &[(String, Metadata)]) -> Result<usize> {
+        if updates.is_empty() {
+            return Ok(0);
+        }
+
+        let table = self.table_name()?;
+        let conn = self.client.get_conn().await?;
+        // Like the DELETE, the UPDATE doesn't report the rows it changed, they are counted first.
+        let uuids = updates.iter().map(|x| x.0.clone()).collect::<Vec<_>>();
+        let count_sql = format!(
+            "SELECT count(*) FROM {} WHERE uuid IN ({})",
+            table,
+            sql_string_list(&uuids)
+        );
+        let (count,): (u64,) = conn.query_row(&count_sql).await?.map_or(Ok((0,)), |row| {
+            row.try_into().map_err(|e: String| anyhow!(e))
+        })?;
+
+        for (uuid, metadata) in updates {
+            let update_sql = format!(
+                "UPDATE {} SET metadata = {} WHERE uuid = {}",
+                table,
+                quote_sql_string(&serde_json::to_string(metadata)?),
+                quote_sql_string(uuid)
+            );
+            conn.exec(&update_sql).await?;
+        }
+        info!("updated the metadata of {} documents in {}", count, table);
+
+        Ok(count as usize)
+    }
+
+    async fn similarity_search_with_filter(
+        &self,
+        query: &str,
+        k: usize,
+        filter: &Filter,
+    ) -> Result<Vec<(Document, f32)>> {
+        let table = self.table_name()?;
         let query_embedding = self.embedding.embed_query(query).await?;
 
         let sql = format!(
-            "SELECT path, content, content_md5, (1- cosine_distance({:?}, embedding)) AS similarity FROM {}.{} \
-             WHERE length(embedding) > 0 AND length(content) > 0 AND similarity > {} ORDER BY similarity DESC LIMIT {}",
-            query_embedding, self.database, self.table, self.min_similarity, k
+            "SELECT path, content, content_md5, (1- cosine_distance({:?}, embedding)) AS similarity, metadata FROM {} \
+             WHERE length(embedding) > 0 AND length(content) > 0 AND similarity > {} AND ({}) ORDER BY similarity DESC LIMIT {}",
+            query_embedding, table, self.min_similarity, filter.to_sql(), k
         );
 
-        info!("similarity_search from {}.{}", self.database, self.table);
+        info!("similarity_search from {}", table);
 
         let mut documents = vec![];
-        type RowResult = (String, String, String, f32);
+        type RowResult = (String, String, String, f32, Option<String>);
         let conn = self.client.get_conn().await?;
         let mut rows = conn.query_iter(&sql).await?;
         while let Some(row) = rows.next().await {
@@ -131,11 +278,19 @@ impl VectorStore for DatabendVectorStore {
 
             info!("document: {:?}", row);
 
-            documents.push(Document {
-                path: row.0,
-                content: row.1,
-                content_md5: row.2,
-            });
+            documents.push((
+                Document {
+                    path: row.0,
+                    content: row.1,
+                    content_md5: row.2,
+                    // The rows inserted before the metadata have none.
+                    metadata: row
+                        .4
+                        .and_then(|x| serde_json::from_str(&x).ok())
+                        .unwrap_or_default(),
+                },
+                row.3,
+            ));
         }
         info!("Found {} documents", documents.len());
 
@@ -0,0 +1,205 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cmp::Ordering;
+
+use crate::quote_sql_string;
+use crate::Document;
+use crate::MetadataValue;
+
+// Restricts a similarity search to the documents it matches.
+// The keys are the metadata keys, except "path" which is the path of the document.
+//
+//   Filter::and(vec![
+//       Filter::eq("repository", "https://github.com/datafuselabs/databend"),
+//       Filter::any_of("language", vec!["rust", "markdown"]),
+//       Filter::path_prefix("docs/"),
+//   ])
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Eq(String, MetadataValue),
+    In(String, Vec<MetadataValue>),
+    // Both bounds are inclusive, the integers compare as numbers and the strings lexicographically.
+    Range {
+        key: String,
+        min: Option<MetadataValue>,
+        max: Option<MetadataValue>,
+    },
+    PathPrefix(String),
+    // All of them, no filter at all when empty.
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::And(vec![])
+    }
+}
+
+impl Filter {
+    pub fn eq<V: Into<MetadataValue>>(key: &str, value: V) -> Self {
+        Filter::Eq(key.to_string(), value.into())
+    }
+
+    pub fn any_of<V: Into<MetadataValue>>(key: &str, values: Vec<V>) -> Self {
+        Filter::In(
+            key.to_string(),
+            values.into_iter().map(Into::into).collect(),
+        )
+    }
+
+    pub fn range<V: Into<MetadataValue>>(key: &str, min: Option<V>, max: Option<V>) -> Self {
+        Filter::Range {
+            key: key.to_string(),
+            min: min.map(Into::into),
+            max: max.map(Into::into),
+        }
+    }
+
+    pub fn path_prefix(prefix: &str) -> Self {
+        Filter::PathPrefix(prefix.to_string())
+    }
+
+    pub fn and(filters: Vec<Filter>) -> Self {
+        Filter::And(filters)
+    }
+
+    pub fn or(filters: Vec<Filter>) -> Self {
+        Filter::Or(filters)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Filter::And(x) if x.is_empty())
+    }
+
+    pub fn matches(&self, document: &Document) -> bool {
+        match self {
+            Filter::Eq(key, value) => value_of(document, key).is_some_and(|x| x == *value),
+            Filter::In(key, values) => value_of(document, key).is_some_and(|x| values.contains(&x)),
+            Filter::Range { key, min, max } => match value_of(document, key) {
+                Some(value) => {
+                    let above = min
+                        .as_ref()
+                        .is_none_or(|x| compare(&value, x).is_some_and(Ordering::is_ge));
+                    let below = max
+                        .as_ref()
+                        .is_none_or(|x| compare(&value, x).is_some_and(Ordering::is_le));
+                    above && below
+                }
+                None => false,
+            },
+            Filter::PathPrefix(prefix) => document.path.starts_with(prefix.as_str()),
+            Filter::And(filters) => filters.iter().all(|x| x.matches(document)),
+            Filter::Or(filters) => filters.iter().any(|x| x.matches(document)),
+        }
+    }
+
+    // The WHERE condition over the columns of the Databend store, the metadata is a JSON string.
+    // Every key and value is quoted as a SQL string, the filter can come from the user.
+    pub fn to_sql(&self) -> String {
+        match self {
+            Filter::Eq(key, value) => compare_sql(key, "=", value),
+            Filter::In(_, values) if values.is_empty() => "1 = 0".to_string(),
+            Filter::In(key, values) => {
+                let conditions = values
+                    .iter()
+                    .map(|x| compare_sql(key, "=", x))
+                    .collect::<Vec<_>>();
+                format!("({})", conditions.join(" OR "))
+            }
+            Filter::Range { key, min, max } => {
+                let mut conditions = vec![];
+                if let Some(min) = min {
+                    conditions.push(compare_sql(key, ">=", min));
+                }
+                if let Some(max) = max {
+                    conditions.push(compare_sql(key, "<=", max));
+                }
+                if !conditions.is_empty() {
+                    format!("({})", conditions.join(" AND "))
+                } else if key == "path" {
+                    "1 = 1".to_string()
+                } else {
+                    format!("{} IS NOT NULL", metadata_sql(key))
+                }
+            }
+            Filter::PathPrefix(prefix) => format!(
+                "LEFT(path, {}) = {}",
+                prefix.chars().count(),
+                quote_sql_string(prefix)
+            ),
+            Filter::And(filters) if filters.is_empty() => "1 = 1".to_string(),
+            Filter::And(filters) => format!(
+                "({})",
+                filters
+                    .iter()
+                    .map(|x| x.to_sql())
+                    .collect::<Vec<_>>()
+                    .join(" AND ")
+            ),
+            Filter::Or(filters) if filters.is_empty() => "1 = 0".to_string(),
+            Filter::Or(filters) => format!(
+                "({})",
+                filters
+                    .iter()
+                    .map(|x| x.to_sql())
+                    .collect::<Vec<_>>()
+                    .join(" OR ")
+            ),
+        }
+    }
+}
+
+fn value_of(document: &Document, key: &str) -> Option<MetadataValue> {
+    match key {
+        "path" => Some(MetadataValue::from(document.path.as_str())),
+        _ => document.metadata.get(key).cloned(),
+    }
+}
+
+// Only the values of the same type compare.
+fn compare(a: &MetadataValue, b: &MetadataValue) -> Option<Ordering> {
+    match (a, b) {
+        (MetadataValue::Integer(a), MetadataValue::Integer(b)) => Some(a.cmp(b)),
+        (MetadataValue::String(a), MetadataValue::String(b)) => Some(a.cmp(b)),
+        (MetadataValue::Boolean(a), MetadataValue::Boolean(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+// The value of the key, `get` takes the key as it is: a dot or a bracket is no path.
+fn metadata_sql(key: &str) -> String {
+    format!("get(parse_json(metadata), {})", quote_sql_string(key))
+}
+
+// Only the values of the same type compare, as in `matches`: the typed accessors are NULL for
+// the values of another type.
+fn compare_sql(key: &str, op: &str, value: &MetadataValue) -> String {
+    if key == "path" {
+        return match value {
+            MetadataValue::String(x) => format!("path {} {}", op, quote_sql_string(x)),
+            _ => "1 = 0".to_string(),
+        };
+    }
+
+    let metadata = metadata_sql(key);
+    match value {
+        MetadataValue::Boolean(x) => format!("as_boolean({}) {} {}", metadata, op, x),
+        MetadataValue::Integer(x) => format!("as_integer({}) {} {}", metadata, op, x),
+        MetadataValue::String(x) => {
+            format!("as_string({}) {} {}", metadata, op, quote_sql_string(x))
+        }
+    }
+}
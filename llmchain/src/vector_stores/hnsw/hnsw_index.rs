@@ -0,0 +1,367 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::SimilarityMetric;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HnswConfig {
+    // The number of neighbours of a node on the upper layers, twice as many on the bottom one.
+    pub m: usize,
+    // The size of the candidate list when inserting, higher builds a better graph, slower.
+    pub ef_construction: usize,
+    // The size of the candidate list when searching, higher gives a better recall, slower.
+    pub ef_search: usize,
+    pub metric: SimilarityMetric,
+    // Seeds the random levels of the nodes, the same inserts always build the same graph.
+    pub seed: u64,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        HnswConfig {
+            m: 16,
+            ef_construction: 200,
+            ef_search: 64,
+            metric: SimilarityMetric::Cosine,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Node {
+    vector: Vec<f32>,
+    // The neighbours on every layer of the node, from the bottom one.
+    neighbors: Vec<Vec<usize>>,
+    deleted: bool,
+}
+
+// A node with its similarity to the query, ordered by similarity.
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    similarity: f32,
+    id: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.similarity
+            .total_cmp(&other.similarity)
+            .then_with(|| other.id.cmp(&self.id))
+    }
+}
+
+// Hierarchical Navigable Small World graph, an approximate nearest neighbours index:
+// https://arxiv.org/abs/1603.09320
+//
+// The nodes are identified by their insertion order. A deleted node stays in the graph to keep it
+// navigable but is never returned, `len` only counts the live nodes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HnswIndex {
+    config: HnswConfig,
+    nodes: Vec<Node>,
+    entry_point: Option<usize>,
+    deleted: usize,
+}
+
+impl HnswIndex {
+    pub fn create(config: HnswConfig) -> Self {
+        HnswIndex {
+            config,
+            nodes: vec![],
+            entry_point: None,
+            deleted: 0,
+        }
+    }
+
+    pub fn config(&self) -> &HnswConfig {
+        &self.config
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len() - self.deleted
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn set_ef_search(&mut self, ef_search: usize) {
+        self.config.ef_search = ef_search.max(1);
+    }
+
+    // Inserts the vector and returns its id.
+    pub fn insert(&mut self, vector: Vec<f32>) -> usize {
+        let id = self.nodes.len();
+        let level = self.random_level(id);
+        self.nodes.push(Node {
+            vector,
+            neighbors: vec![vec![]; level + 1],
+            deleted: false,
+        });
+
+        let entry_point = match self.entry_point {
+            Some(entry_point) => entry_point,
+            None => {
+                self.entry_point = Some(id);
+                return id;
+            }
+        };
+
+        let query = self.nodes[id].vector.clone();
+        let top_level = self.nodes[entry_point].neighbors.len() - 1;
+        let mut entry_points = vec![self.candidate(&query, entry_point)];
+        for layer in (level + 1..=top_level).rev() {
+            entry_points = self.search_layer(&query, &entry_points, 1, layer);
+        }
+
+        for layer in (0..=level.min(top_level)).rev() {
+            let candidates =
+                self.search_layer(&query, &entry_points, self.config.ef_construction, layer);
+            let max_neighbors = self.max_neighbors(layer);
+            let neighbors = self.select_neighbors(&candidates, self.config.m);
+
+            for neighbor in &neighbors {
+                self.nodes[*neighbor].neighbors[layer].push(id);
+                if self.nodes[*neighbor].neighbors[layer].len() > max_neighbors {
+                    self.prune(*neighbor, layer, max_neighbors);
+                }
+            }
+            self.nodes[id].neighbors[layer] = neighbors;
+            entry_points = candidates;
+        }
+
+        if level > top_level {
+            self.entry_point = Some(id);
+        }
+        id
+    }
+
+    // Returns whether the node was live.
+    pub fn delete(&mut self, id: usize) -> bool {
+        match self.nodes.get_mut(id) {
+            Some(node) if !node.deleted => {
+                node.deleted = true;
+                self.deleted += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.nodes.get(id).is_some_and(|x| !x.deleted)
+    }
+
+    // The vector of the node, deleted or not.
+    pub fn vector(&self, id: usize) -> Option<&[f32]> {
+        self.nodes.get(id).map(|x| x.vector.as_slice())
+    }
+
+    // The k most similar live nodes with their similarity, the most similar first.
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(usize, f32)> {
+        let entry_point = match self.entry_point {
+            Some(entry_point) if k > 0 => entry_point,
+            _ => return vec![],
+        };
+
+        let top_level = self.nodes[entry_point].neighbors.len() - 1;
+        let mut entry_points = vec![self.candidate(query, entry_point)];
+        for layer in (1..=top_level).rev() {
+            entry_points = self.search_layer(query, &entry_points, 1, layer);
+        }
+
+        let ef = self.config.ef_search.max(k);
+        self.search_layer_live(query, &entry_points, ef, 0)
+            .into_iter()
+            .take(k)
+            .map(|x| (x.id, x.similarity))
+            .collect()
+    }
+
+    // Every live node compared to the query, the exact answer the graph approximates.
+    pub fn brute_force_search(&self, query: &[f32], k: usize) -> Vec<(usize, f32)> {
+        let mut candidates = (0..self.nodes.len())
+            .filter(|x| !self.nodes[*x].deleted)
+            .map(|x| self.candidate(query, x))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| b.cmp(a));
+        candidates
+            .into_iter()
+            .take(k)
+            .map(|x| (x.id, x.similarity))
+            .collect()
+    }
+
+    fn candidate(&self, query: &[f32], id: usize) -> Candidate {
+        Candidate {
+            similarity: self.config.metric.similarity(query, &self.nodes[id].vector),
+            id,
+        }
+    }
+
+    fn max_neighbors(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.config.m * 2
+        } else {
+            self.config.m
+        }
+    }
+
+    // Levels follow a geometric distribution, each level holds about 1/m of the one below.
+    fn random_level(&self, id: usize) -> usize {
+        // splitmix64.
+        let mut z = self
+            .config
+            .seed
+            .wrapping_add((id as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+
+        let uniform = ((z >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        let ml = 1.0 / (self.config.m.max(2) as f64).ln();
+        (-uniform.ln() * ml).floor() as usize
+    }
+
+    fn prune(&mut self, id: usize, layer: usize, max_neighbors: usize) {
+        let vector = &self.nodes[id].vector;
+        let mut neighbors = self.nodes[id].neighbors[layer]
+            .iter()
+            .map(|x| self.candidate(vector, *x))
+            .collect::<Vec<_>>();
+        neighbors.sort_by(|a, b| b.cmp(a));
+        self.nodes[id].neighbors[layer] = self.select_neighbors(&neighbors, max_neighbors);
+    }
+
+    // The heuristic of the paper: a candidate is kept only if it is more similar to the node than to
+    // the neighbours kept so far, the links spread in all the directions instead of into one cluster.
+    // The candidates are sorted, the most similar first.
+    fn select_neighbors(&self, candidates: &[Candidate], max_neighbors: usize) -> Vec<usize> {
+        let mut selected: Vec<Candidate> = Vec::with_capacity(max_neighbors);
+        for candidate in candidates {
+            if selected.len() >= max_neighbors {
+                break;
+            }
+            let vector = &self.nodes[candidate.id].vector;
+            let diverse = selected.iter().all(|x| {
+                self.config
+                    .metric
+                    .similarity(vector, &self.nodes[x.id].vector)
+                    < candidate.similarity
+            });
+            if diverse {
+                selected.push(*candidate);
+            }
+        }
+        selected.into_iter().map(|x| x.id).collect()
+    }
+
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entry_points: &[Candidate],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Candidate> {
+        self.search_layer_with(query, entry_points, ef, layer, false)
+    }
+
+    // The deleted nodes are walked through but kept out of the results.
+    fn search_layer_live(
+        &self,
+        query: &[f32],
+        entry_points: &[Candidate],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Candidate> {
+        self.search_layer_with(query, entry_points, ef, layer, true)
+    }
+
+    // The ef nodes of the layer most similar to the query, found greedily from the entry points,
+    // the most similar first.
+    fn search_layer_with(
+        &self,
+        query: &[f32],
+        entry_points: &[Candidate],
+        ef: usize,
+        layer: usize,
+        live_only: bool,
+    ) -> Vec<Candidate> {
+        let is_result = |x: &Candidate| !live_only || !self.nodes[x.id].deleted;
+        let mut visited = vec![false; self.nodes.len()];
+        entry_points.iter().for_each(|x| visited[x.id] = true);
+        // The candidates to expand, the most similar on top.
+        let mut candidates = entry_points.iter().copied().collect::<BinaryHeap<_>>();
+        // The results so far, the least similar on top.
+        let mut results = entry_points
+            .iter()
+            .filter(|x| is_result(x))
+            .map(|x| std::cmp::Reverse(*x))
+            .collect::<BinaryHeap<_>>();
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(candidate) = candidates.pop() {
+            let worst = results.peek().map(|x| x.0);
+            if worst.is_some_and(|x| candidate < x) && results.len() >= ef {
+                break;
+            }
+
+            for neighbor in &self.nodes[candidate.id].neighbors[layer] {
+                if visited[*neighbor] {
+                    continue;
+                }
+                visited[*neighbor] = true;
+
+                let neighbor = self.candidate(query, *neighbor);
+                let worst = results.peek().map(|x| x.0);
+                if results.len() < ef || worst.is_some_and(|x| neighbor > x) {
+                    candidates.push(neighbor);
+                    if is_result(&neighbor) {
+                        results.push(std::cmp::Reverse(neighbor));
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut results = results.into_iter().map(|x| x.0).collect::<Vec<_>>();
+        results.sort_by(|a, b| b.cmp(a));
+        results
+    }
+}
@@ -0,0 +1,326 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::bail;
+use anyhow::Result;
+use log::info;
+use log::warn;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::Document;
+use crate::DocumentKey;
+use crate::Documents;
+use crate::Embedding;
+use crate::Filter;
+use crate::HnswConfig;
+use crate::HnswIndex;
+use crate::Metadata;
+use crate::SimilarityMetric;
+use crate::VectorStore;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Record {
+    uuid: String,
+    document: Document,
+}
+
+// The index and the documents, by node id.
+#[derive(Serialize, Deserialize)]
+struct State {
+    index: HnswIndex,
+    // None for the deleted nodes.
+    records: Vec<Option<Record>>,
+}
+
+// An in-process store searching an HNSW index instead of comparing the query with every document,
+// for the corpora too large for `InMemoryVectorStore`.
+pub struct HnswVectorStore {
+    embedding: Arc<dyn Embedding>,
+    min_similarity: f32,
+    state: RwLock<State>,
+    uuids: RwLock<HashMap<String, usize>>,
+}
+
+impl HnswVectorStore {
+    pub fn create(embedding: Arc<dyn Embedding>) -> Self {
+        HnswVectorStore {
+            embedding,
+            min_similarity: 0.5,
+            state: RwLock::new(State {
+                index: HnswIndex::create(HnswConfig::default()),
+                records: vec![],
+            }),
+            uuids: RwLock::new(HashMap::new()),
+        }
+    }
+
+    // The graph settings apply to an empty store only, the inserted nodes keep the graph they were
+    // built with: on a store with documents, such as a loaded one, the config is ignored with a warning.
+    pub fn with_config(self, config: HnswConfig) -> Self {
+        {
+            let mut state = self.state.write();
+            if state.records.is_empty() {
+                state.index = HnswIndex::create(config);
+            } else {
+                warn!(
+                    "the hnsw store has {} documents already, the config is ignored",
+                    state.records.len()
+                );
+            }
+        }
+        self
+    }
+
+    pub fn with_metric(self, metric: SimilarityMetric) -> Self {
+        let config = HnswConfig {
+            metric,
+            ..*self.state.read().index.config()
+        };
+        self.with_config(config)
+    }
+
+    // Can be changed anytime, it only affects the searches.
+    pub fn with_ef_search(self, ef_search: usize) -> Self {
+        self.state.write().index.set_ef_search(ef_search);
+        self
+    }
+
+    pub fn with_min_similarity(mut self, similarity: f32) -> Self {
+        self.min_similarity = similarity;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.uuids.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.uuids.read().is_empty()
+    }
+
+    // Writes the graph and the documents as JSON, the graph isn't rebuilt on load.
+    // The deleted documents are dropped from the graph first.
+    pub fn save(&self, path: &str) -> Result<()> {
+        self.compact();
+        let content = serde_json::to_string(&*self.state.read())?;
+        let path = Path::new(path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // Written aside then renamed, a crash never leaves a partial file.
+        let tmp = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, path)?;
+
+        info!("saved {} documents to {}", self.len(), path.display());
+        Ok(())
+    }
+
+    // Rebuilds the graph from the live documents, the deleted ones stay in it to keep it navigable
+    // until then. The ids change, the uuids don't.
+    fn compact(&self) {
+        let mut state = self.state.write();
+        let mut uuids = self.uuids.write();
+        if state.records.iter().all(Option::is_some) {
+            return;
+        }
+
+        let mut index = HnswIndex::create(*state.index.config());
+        let mut records = Vec::with_capacity(uuids.len());
+        uuids.clear();
+        for (id, record) in state.records.iter().enumerate() {
+            let Some(record) = record else {
+                continue;
+            };
+            let vector = state.index.vector(id).unwrap_or_default().to_vec();
+            let new_id = index.insert(vector);
+            // The empty documents are stored but never searched.
+            if !state.index.contains(id) {
+                index.delete(new_id);
+            }
+            uuids.insert(record.uuid.clone(), new_id);
+            records.push(Some(record.clone()));
+        }
+        info!(
+            "compacted the hnsw graph from {} to {} nodes",
+            state.records.len(),
+            records.len()
+        );
+
+        state.index = index;
+        state.records = records;
+    }
+
+    // Replaces the graph and the documents by the ones saved in the file.
+    pub fn load(&self, path: &str) -> Result<()> {
+        let state: State = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let uuids: HashMap<String, usize> = state
+            .records
+            .iter()
+            .enumerate()
+            .filter_map(|(id, x)| x.as_ref().map(|x| (x.uuid.clone(), id)))
+            .collect();
+        info!("loaded {} documents from {}", uuids.len(), path);
+
+        *self.state.write() = state;
+        *self.uuids.write() = uuids;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl VectorStore for HnswVectorStore {
+    async fn init(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn add_documents(&self, inputs: &Documents) -> Result<Vec<String>> {
+        let embeddings = self.embedding.embed_documents(inputs).await?;
+        if embeddings.len() != inputs.len() {
+            bail!(
+                "got {} embeddings for {} documents",
+                embeddings.len(),
+                inputs.len()
+            );
+        }
+
+        let mut uuids = Vec::with_capacity(inputs.len());
+        let mut state = self.state.write();
+        let mut ids = self.uuids.write();
+        for (document, embedding) in inputs.iter().zip(embeddings) {
+            // An empty document is stored but never searched, as the other stores skip them.
+            let id = state.index.insert(embedding);
+            if document.content.is_empty() {
+                state.index.delete(id);
+            }
+
+            let uuid = Uuid::new_v4().to_string();
+            state.records.push(Some(Record {
+                uuid: uuid.clone(),
+                document,
+            }));
+            ids.insert(uuid.clone(), id);
+            uuids.push(uuid);
+        }
+
+        Ok(uuids)
+    }
+
+    async fn delete(&self, uuids: &[String]) -> Result<usize> {
+        let mut state = self.state.write();
+        let mut ids = self.uuids.write();
+        let mut deleted = 0;
+        for uuid in uuids {
+            if let Some(id) = ids.remove(uuid) {
+                state.index.delete(id);
+                state.records[id] = None;
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    }
+
+    async fn delete_by_path(&self, path: &str) -> Result<usize> {
+        let uuids = self
+            .state
+            .read()
+            .records
+            .iter()
+            .flatten()
+            .filter(|x| x.document.path == path)
+            .map(|x| x.uuid.clone())
+            .collect::<Vec<_>>();
+        self.delete(&uuids).await
+    }
+
+    async fn document_keys(&self, paths: &[String]) -> Result<Vec<DocumentKey>> {
+        let paths = paths.iter().collect::<HashSet<_>>();
+        Ok(self
+            .state
+            .read()
+            .records
+            .iter()
+            .flatten()
+            .filter(|x| paths.contains(&x.document.path))
+            .map(|x| DocumentKey {
+                uuid: x.uuid.clone(),
+                path: x.document.path.clone(),
+                content_md5: x.document.content_md5.clone(),
+                metadata: x.document.metadata.clone(),
+            })
+            .collect())
+    }
+
+    async fn update_metadata(&self, updates: &[(String, Metadata)]) -> Result<usize> {
+        let mut state = self.state.write();
+        let ids = self.uuids.read();
+        let mut updated = 0;
+        for (uuid, metadata) in updates {
+            if let Some(record) = ids.get(uuid).and_then(|id| state.records[*id].as_mut()) {
+                record.document.metadata = metadata.clone();
+                updated += 1;
+            }
+        }
+        Ok(updated)
+    }
+
+    async fn similarity_search_with_filter(
+        &self,
+        query: &str,
+        k: usize,
+        filter: &Filter,
+    ) -> Result<Vec<(Document, f32)>> {
+        let query_embedding = self.embedding.embed_query(query).await?;
+
+        // The index knows nothing about the filter, search wider until k documents match it
+        // or the whole index has been searched.
+        let state = self.state.read();
+        let mut limit = k;
+        let documents = loop {
+            let found = state.index.search(&query_embedding, limit);
+            let exhausted = found.len() < limit;
+            let below = found
+                .last()
+                .is_some_and(|(_, similarity)| *similarity <= self.min_similarity);
+
+            let documents = found
+                .into_iter()
+                .filter(|(_, similarity)| *similarity > self.min_similarity)
+                .filter_map(|(id, similarity)| {
+                    let record = state.records[id].as_ref()?;
+                    Some((record.document.clone(), similarity))
+                })
+                .filter(|(document, _)| filter.matches(document))
+                .take(k)
+                .collect::<Vec<_>>();
+            if documents.len() >= k || exhausted || below || filter.is_empty() {
+                break documents;
+            }
+            limit = limit.saturating_mul(2);
+        };
+        info!("Found {} documents", documents.len());
+
+        Ok(documents)
+    }
+}
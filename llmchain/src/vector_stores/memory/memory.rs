@@ -0,0 +1,262 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::bail;
+use anyhow::Result;
+use log::info;
+use parking_lot::RwLock;
+use serde::Deserialize;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::Document;
+use crate::DocumentKey;
+use crate::Documents;
+use crate::Embedding;
+use crate::Filter;
+use crate::Metadata;
+use crate::VectorStore;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SimilarityMetric {
+    // 1 - cosine distance, as the Databend store.
+    Cosine,
+    // The dot product, the same as cosine for the unit vectors.
+    Dot,
+    // 1 / (1 + euclidean distance), in (0, 1] so the min similarity still applies.
+    L2,
+}
+
+impl SimilarityMetric {
+    pub fn similarity(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            SimilarityMetric::Cosine => {
+                let dot = dot(a, b);
+                let norm = magnitude(a) * magnitude(b);
+                if norm == 0.0 {
+                    0.0
+                } else {
+                    dot / norm
+                }
+            }
+            SimilarityMetric::Dot => dot(a, b),
+            SimilarityMetric::L2 => {
+                let distance = a
+                    .iter()
+                    .zip(b)
+                    .map(|(x, y)| (x - y) * (x - y))
+                    .sum::<f32>()
+                    .sqrt();
+                1.0 / (1.0 + distance)
+            }
+        }
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn magnitude(a: &[f32]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Record {
+    uuid: String,
+    document: Document,
+    embedding: Vec<f32>,
+}
+
+// The file written by `save`.
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    records: Vec<Record>,
+}
+
+// Keeps the documents and their embeddings in memory, the search compares the query with all of them.
+// Fits the small corpora and the tests, save it to a local file to reuse the embeddings.
+pub struct InMemoryVectorStore {
+    embedding: Arc<dyn Embedding>,
+    metric: SimilarityMetric,
+    min_similarity: f32,
+    records: RwLock<Vec<Record>>,
+}
+
+impl InMemoryVectorStore {
+    pub fn create(embedding: Arc<dyn Embedding>) -> Self {
+        InMemoryVectorStore {
+            embedding,
+            metric: SimilarityMetric::Cosine,
+            min_similarity: 0.5,
+            records: RwLock::new(vec![]),
+        }
+    }
+
+    pub fn with_metric(mut self, metric: SimilarityMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub fn with_min_similarity(mut self, similarity: f32) -> Self {
+        self.min_similarity = similarity;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.read().is_empty()
+    }
+
+    // Writes the documents and their embeddings as JSON.
+    pub fn save(&self, path: &str) -> Result<()> {
+        let snapshot = Snapshot {
+            records: self.records.read().clone(),
+        };
+        let path = Path::new(path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // Written aside then renamed, a crash never leaves a partial file.
+        let tmp = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+        fs::write(&tmp, serde_json::to_string(&snapshot)?)?;
+        fs::rename(&tmp, path)?;
+
+        info!(
+            "saved {} documents to {}",
+            snapshot.records.len(),
+            path.display()
+        );
+        Ok(())
+    }
+
+    // Replaces the documents by the ones saved in the file.
+    pub fn load(&self, path: &str) -> Result<()> {
+        let snapshot: Snapshot = serde_json::from_str(&fs::read_to_string(path)?)?;
+        info!("loaded {} documents from {}", snapshot.records.len(), path);
+        *self.records.write() = snapshot.records;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl VectorStore for InMemoryVectorStore {
+    async fn init(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn add_documents(&self, inputs: &Documents) -> Result<Vec<String>> {
+        let embeddings = self.embedding.embed_documents(inputs).await?;
+        if embeddings.len() != inputs.len() {
+            bail!(
+                "got {} embeddings for {} documents",
+                embeddings.len(),
+                inputs.len()
+            );
+        }
+
+        let mut uuids = Vec::with_capacity(inputs.len());
+        let mut records = self.records.write();
+        for (document, embedding) in inputs.iter().zip(embeddings) {
+            let uuid = Uuid::new_v4().to_string();
+            uuids.push(uuid.clone());
+            records.push(Record {
+                uuid,
+                document,
+                embedding,
+            });
+        }
+
+        Ok(uuids)
+    }
+
+    async fn delete(&self, uuids: &[String]) -> Result<usize> {
+        let uuids = uuids.iter().collect::<HashSet<_>>();
+        let mut records = self.records.write();
+        let len = records.len();
+        records.retain(|x| !uuids.contains(&x.uuid));
+        Ok(len - records.len())
+    }
+
+    async fn delete_by_path(&self, path: &str) -> Result<usize> {
+        let mut records = self.records.write();
+        let len = records.len();
+        records.retain(|x| x.document.path != path);
+        Ok(len - records.len())
+    }
+
+    async fn document_keys(&self, paths: &[String]) -> Result<Vec<DocumentKey>> {
+        let paths = paths.iter().collect::<HashSet<_>>();
+        Ok(self
+            .records
+            .read()
+            .iter()
+            .filter(|x| paths.contains(&x.document.path))
+            .map(|x| DocumentKey {
+                uuid: x.uuid.clone(),
+                path: x.document.path.clone(),
+                content_md5: x.document.content_md5.clone(),
+                metadata: x.document.metadata.clone(),
+            })
+            .collect())
+    }
+
+    async fn update_metadata(&self, updates: &[(String, Metadata)]) -> Result<usize> {
+        let updates = updates.iter().cloned().collect::<HashMap<_, _>>();
+        let mut updated = 0;
+        for record in self.records.write().iter_mut() {
+            if let Some(metadata) = updates.get(&record.uuid) {
+                record.document.metadata = metadata.clone();
+                updated += 1;
+            }
+        }
+        Ok(updated)
+    }
+
+    async fn similarity_search_with_filter(
+        &self,
+        query: &str,
+        k: usize,
+        filter: &Filter,
+    ) -> Result<Vec<(Document, f32)>> {
+        let query_embedding = self.embedding.embed_query(query).await?;
+
+        let records = self.records.read();
+        let mut scored = records
+            .iter()
+            .filter(|x| !x.embedding.is_empty() && !x.document.content.is_empty())
+            .filter(|x| filter.matches(&x.document))
+            .map(|x| (self.metric.similarity(&query_embedding, &x.embedding), x))
+            .filter(|(similarity, _)| *similarity > self.min_similarity)
+            .collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.truncate(k);
+        info!("Found {} documents", scored.len());
+
+        Ok(scored
+            .into_iter()
+            .map(|(similarity, x)| (x.document.clone(), similarity))
+            .collect())
+    }
+}
@@ -13,7 +13,20 @@
 // limitations under the License.
 
 mod databend;
+mod filter;
+mod hnsw;
+mod memory;
+mod sqlite;
 mod vector_store;
 
 pub use databend::DatabendVectorStore;
+pub use filter::Filter;
+pub use hnsw::HnswConfig;
+pub use hnsw::HnswIndex;
+pub use hnsw::HnswVectorStore;
+pub use memory::InMemoryVectorStore;
+pub use memory::SimilarityMetric;
+pub use sqlite::SqliteVectorStore;
+pub use vector_store::DocumentKey;
+pub(crate) use vector_store::UpsertPlan;
 pub use vector_store::VectorStore;
@@ -0,0 +1,386 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use anyhow::bail;
+use anyhow::Result;
+use log::info;
+use parking_lot::Mutex;
+use rusqlite::params;
+use rusqlite::params_from_iter;
+use rusqlite::types::Value;
+use rusqlite::Connection;
+use uuid::Uuid;
+
+use crate::validate_sql_identifier;
+use crate::Document;
+use crate::DocumentKey;
+use crate::Documents;
+use crate::Embedding;
+use crate::Filter;
+use crate::Metadata;
+use crate::MetadataValue;
+use crate::SimilarityMetric;
+use crate::VectorStore;
+
+// A single-file store: the documents, their metadata as a JSON object and their embeddings as blobs of
+// little-endian f32 live in one SQLite table, the similarity is computed in Rust.
+pub struct SqliteVectorStore {
+    connection: Arc<Mutex<Connection>>,
+    table: String,
+    embedding: Arc<dyn Embedding>,
+    metric: SimilarityMetric,
+    min_similarity: f32,
+}
+
+impl SqliteVectorStore {
+    // The path of the database file, or ":memory:".
+    pub fn create(path: &str, embedding: Arc<dyn Embedding>) -> Result<Self> {
+        Ok(SqliteVectorStore {
+            connection: Arc::new(Mutex::new(Connection::open(path)?)),
+            table: "llmchain_collection".to_string(),
+            embedding,
+            metric: SimilarityMetric::Cosine,
+            min_similarity: 0.5,
+        })
+    }
+
+    pub fn with_table(mut self, table: &str) -> Self {
+        self.table = table.to_string();
+        self
+    }
+
+    pub fn with_metric(mut self, metric: SimilarityMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub fn with_min_similarity(mut self, similarity: f32) -> Self {
+        self.min_similarity = similarity;
+        self
+    }
+
+    // The name is checked before every query, it's formatted into the SQL as it is.
+    fn table_name(&self) -> Result<String> {
+        validate_sql_identifier(&self.table)?;
+        Ok(self.table.clone())
+    }
+}
+
+#[async_trait::async_trait]
+impl VectorStore for SqliteVectorStore {
+    async fn init(&self) -> Result<()> {
+        let table = self.table_name()?;
+        let table_create_sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} \
+            (uuid TEXT PRIMARY KEY, path TEXT NOT NULL, content TEXT NOT NULL, content_md5 TEXT NOT NULL, \
+            metadata TEXT NOT NULL DEFAULT '{{}}', embedding BLOB NOT NULL)",
+            table
+        );
+        // The upserts look the documents up by path.
+        let index_create_sql = format!(
+            "CREATE INDEX IF NOT EXISTS {}_path ON {} (path)",
+            table, table
+        );
+
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let connection = connection.lock();
+            connection.execute(&table_create_sql, [])?;
+            connection.execute(&index_create_sql, [])?;
+            Ok(())
+        })
+        .await?
+    }
+
+    async fn add_documents(&self, inputs: &Documents) -> Result<Vec<String>> {
+        let embeddings = self.embedding.embed_documents(inputs).await?;
+        if embeddings.len() != inputs.len() {
+            bail!(
+                "got {} embeddings for {} documents",
+                embeddings.len(),
+                inputs.len()
+            );
+        }
+        let uuids = (0..inputs.len())
+            .map(|_| Uuid::new_v4().to_string())
+            .collect::<Vec<_>>();
+
+        let rows = inputs
+            .iter()
+            .zip(embeddings)
+            .zip(uuids.clone())
+            .map(|((document, embedding), uuid)| {
+                let metadata = serde_json::to_string(&document.metadata)?;
+                Ok((uuid, document, metadata, embedding))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let connection = self.connection.clone();
+        let sql = format!(
+            "INSERT INTO {} (uuid, path, content, content_md5, metadata, embedding) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            self.table_name()?
+        );
+        tokio::task::spawn_blocking(move || -> Result<()> {
+            let mut connection = connection.lock();
+            let transaction = connection.transaction()?;
+            {
+                let mut statement = transaction.prepare(&sql)?;
+                for (uuid, document, metadata, embedding) in rows {
+                    statement.execute(params![
+                        uuid,
+                        document.path,
+                        document.content,
+                        document.content_md5,
+                        metadata,
+                        embedding_to_blob(&embedding)
+                    ])?;
+                }
+            }
+            transaction.commit()?;
+            Ok(())
+        })
+        .await??;
+
+        Ok(uuids)
+    }
+
+    async fn delete(&self, uuids: &[String]) -> Result<usize> {
+        let connection = self.connection.clone();
+        let sql = format!("DELETE FROM {} WHERE uuid = ?1", self.table_name()?);
+        let uuids = uuids.to_vec();
+        tokio::task::spawn_blocking(move || -> Result<usize> {
+            let mut connection = connection.lock();
+            let transaction = connection.transaction()?;
+            let mut deleted = 0;
+            {
+                let mut statement = transaction.prepare(&sql)?;
+                for uuid in uuids {
+                    deleted += statement.execute(params![uuid])?;
+                }
+            }
+            transaction.commit()?;
+            Ok(deleted)
+        })
+        .await?
+    }
+
+    async fn delete_by_path(&self, path: &str) -> Result<usize> {
+        let connection = self.connection.clone();
+        let sql = format!("DELETE FROM {} WHERE path = ?1", self.table_name()?);
+        let path = path.to_string();
+        tokio::task::spawn_blocking(move || -> Result<usize> {
+            Ok(connection.lock().execute(&sql, params![path])?)
+        })
+        .await?
+    }
+
+    async fn document_keys(&self, paths: &[String]) -> Result<Vec<DocumentKey>> {
+        let connection = self.connection.clone();
+        let sql = format!(
+            "SELECT uuid, path, content_md5, metadata FROM {} WHERE path = ?1",
+            self.table_name()?
+        );
+        let paths = paths.to_vec();
+        tokio::task::spawn_blocking(move || -> Result<Vec<DocumentKey>> {
+            let connection = connection.lock();
+            let mut statement = connection.prepare(&sql)?;
+            let mut keys = vec![];
+            for path in paths {
+                let mut rows = statement.query(params![path])?;
+                while let Some(row) = rows.next()? {
+                    keys.push(DocumentKey {
+                        uuid: row.get(0)?,
+                        path: row.get(1)?,
+                        content_md5: row.get(2)?,
+                        metadata: serde_json::from_str(&row.get::<_, String>(3)?)?,
+                    });
+                }
+            }
+            Ok(keys)
+        })
+        .await?
+    }
+
+    async fn update_metadata(&self, updates: &[(String, Metadata)]) -> Result<usize> {
+        let connection = self.connection.clone();
+        let sql = format!(
+            "UPDATE {} SET metadata = ?1 WHERE uuid = ?2",
+            self.table_name()?
+        );
+        let updates = updates
+            .iter()
+            .map(|(uuid, metadata)| Ok((uuid.clone(), serde_json::to_string(metadata)?)))
+            .collect::<Result<Vec<_>>>()?;
+        tokio::task::spawn_blocking(move || -> Result<usize> {
+            let mut connection = connection.lock();
+            let transaction = connection.transaction()?;
+            let mut updated = 0;
+            {
+                let mut statement = transaction.prepare(&sql)?;
+                for (uuid, metadata) in updates {
+                    updated += statement.execute(params![metadata, uuid])?;
+                }
+            }
+            transaction.commit()?;
+            Ok(updated)
+        })
+        .await?
+    }
+
+    async fn similarity_search_with_filter(
+        &self,
+        query: &str,
+        k: usize,
+        filter: &Filter,
+    ) -> Result<Vec<(Document, f32)>> {
+        let table = self.table_name()?;
+        let query_embedding = self.embedding.embed_query(query).await?;
+
+        let mut params = vec![];
+        let sql = format!(
+            "SELECT path, content, content_md5, embedding, metadata FROM {} \
+             WHERE length(embedding) > 0 AND length(content) > 0 AND ({})",
+            table,
+            filter_sql(filter, &mut params)
+        );
+        info!("similarity_search from {}", table);
+
+        let connection = self.connection.clone();
+        let metric = self.metric;
+        let min_similarity = self.min_similarity;
+        let mut scored = tokio::task::spawn_blocking(move || -> Result<Vec<(f32, Document)>> {
+            let connection = connection.lock();
+            let mut statement = connection.prepare(&sql)?;
+            let mut rows = statement.query(params_from_iter(params))?;
+
+            let mut scored = vec![];
+            while let Some(row) = rows.next()? {
+                let embedding = blob_to_embedding(&row.get::<_, Vec<u8>>(3)?);
+                let similarity = metric.similarity(&query_embedding, &embedding);
+                if similarity <= min_similarity {
+                    continue;
+                }
+
+                scored.push((similarity, Document {
+                    path: row.get(0)?,
+                    content: row.get(1)?,
+                    content_md5: row.get(2)?,
+                    metadata: serde_json::from_str(&row.get::<_, String>(4)?)?,
+                }));
+            }
+            Ok(scored)
+        })
+        .await??;
+
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.truncate(k);
+        info!("Found {} documents", scored.len());
+
+        Ok(scored
+            .into_iter()
+            .map(|(similarity, x)| (x, similarity))
+            .collect())
+    }
+}
+
+// The condition of the filter over the columns of the table, the keys and the values are bound
+// parameters. The metadata keys are looked up with json_each, any key works, even with dots or quotes.
+fn filter_sql(filter: &Filter, params: &mut Vec<Value>) -> String {
+    match filter {
+        Filter::Eq(key, value) => compare_sql(key, "=", value, params),
+        Filter::In(_, values) if values.is_empty() => "1 = 0".to_string(),
+        Filter::In(key, values) => {
+            let conditions = values
+                .iter()
+                .map(|x| compare_sql(key, "=", x, params))
+                .collect::<Vec<_>>();
+            format!("({})", conditions.join(" OR "))
+        }
+        Filter::Range { key, min, max } => {
+            let mut conditions = vec![];
+            if let Some(min) = min {
+                conditions.push(compare_sql(key, ">=", min, params));
+            }
+            if let Some(max) = max {
+                conditions.push(compare_sql(key, "<=", max, params));
+            }
+            if !conditions.is_empty() {
+                format!("({})", conditions.join(" AND "))
+            } else if key == "path" {
+                "1 = 1".to_string()
+            } else {
+                params.push(Value::Text(key.clone()));
+                "EXISTS (SELECT 1 FROM json_each(metadata) WHERE key = ?)".to_string()
+            }
+        }
+        Filter::PathPrefix(prefix) => {
+            params.push(Value::Text(prefix.clone()));
+            format!("substr(path, 1, {}) = ?", prefix.chars().count())
+        }
+        Filter::And(filters) if filters.is_empty() => "1 = 1".to_string(),
+        Filter::And(filters) => {
+            let conditions = filters
+                .iter()
+                .map(|x| filter_sql(x, params))
+                .collect::<Vec<_>>();
+            format!("({})", conditions.join(" AND "))
+        }
+        Filter::Or(filters) if filters.is_empty() => "1 = 0".to_string(),
+        Filter::Or(filters) => {
+            let conditions = filters
+                .iter()
+                .map(|x| filter_sql(x, params))
+                .collect::<Vec<_>>();
+            format!("({})", conditions.join(" OR "))
+        }
+    }
+}
+
+// Only the values of the same type compare, as in `Filter::matches`.
+fn compare_sql(key: &str, op: &str, value: &MetadataValue, params: &mut Vec<Value>) -> String {
+    if key == "path" {
+        return match value {
+            MetadataValue::String(x) => {
+                params.push(Value::Text(x.clone()));
+                format!("path {} ?", op)
+            }
+            _ => "1 = 0".to_string(),
+        };
+    }
+
+    // The JSON booleans are the 'true' and 'false' types, with the values 1 and 0.
+    let (types, value) = match value {
+        MetadataValue::Boolean(x) => ("'true', 'false'", Value::Integer(*x as i64)),
+        MetadataValue::Integer(x) => ("'integer'", Value::Integer(*x)),
+        MetadataValue::String(x) => ("'text'", Value::Text(x.clone())),
+    };
+    params.push(Value::Text(key.to_string()));
+    params.push(value);
+    format!(
+        "EXISTS (SELECT 1 FROM json_each(metadata) WHERE key = ? AND type IN ({}) AND value {} ?)",
+        types, op
+    )
+}
+
+fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn blob_to_embedding(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|x| f32::from_le_bytes(x.try_into().unwrap()))
+        .collect()
+}
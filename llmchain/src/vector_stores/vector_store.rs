@@ -12,14 +12,197 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::hash_map::Entry;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+use anyhow::bail;
 use anyhow::Result;
+use log::info;
 
 use crate::Document;
 use crate::Documents;
+use crate::Filter;
+use crate::Metadata;
+
+// A stored document, without its content and embedding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentKey {
+    pub uuid: String,
+    pub path: String,
+    pub content_md5: String,
+    pub metadata: Metadata,
+}
 
 #[async_trait::async_trait]
 pub trait VectorStore: Send + Sync {
     async fn init(&self) -> Result<()>;
     async fn add_documents(&self, inputs: &Documents) -> Result<Vec<String>>;
-    async fn similarity_search(&self, query: &str, k: usize) -> Result<Vec<Document>>;
+
+    // Returns the number of the documents deleted, the unknown uuids are ignored.
+    async fn delete(&self, uuids: &[String]) -> Result<usize>;
+    async fn delete_by_path(&self, path: &str) -> Result<usize>;
+
+    // The stored documents of the paths.
+    async fn document_keys(&self, paths: &[String]) -> Result<Vec<DocumentKey>>;
+
+    // Replaces the metadata of the stored documents, the embeddings are kept.
+    // Returns the number of the documents updated, the unknown uuids are ignored.
+    async fn update_metadata(&self, updates: &[(String, Metadata)]) -> Result<usize>;
+
+    // Makes the stored documents of each path the given ones, keyed on the path and the content md5:
+    // the unchanged documents are kept and get the new metadata, the new ones embedded and added, the
+    // others of these paths and the duplicates deleted. Returns the uuid of each input, stored before or not.
+    async fn upsert_documents(&self, inputs: &Documents) -> Result<Vec<String>> {
+        let keys = self.document_keys(&UpsertPlan::paths(inputs)).await?;
+        let plan = UpsertPlan::create(inputs, keys);
+        info!(
+            "upsert {} documents: {} unchanged, {} updated, {} added, {} deleted",
+            inputs.len(),
+            inputs.len() - plan.added.len() - plan.updated.len(),
+            plan.updated.len(),
+            plan.added.len(),
+            plan.stale.len()
+        );
+
+        // Added first, a failure leaves the previous documents searchable.
+        let added_uuids = if plan.added.is_empty() {
+            vec![]
+        } else {
+            self.add_documents(&plan.added).await?
+        };
+        if added_uuids.len() != plan.added.len() {
+            bail!(
+                "added {} documents, got {} uuids",
+                plan.added.len(),
+                added_uuids.len()
+            );
+        }
+        if !plan.updated.is_empty() {
+            self.update_metadata(&plan.updated).await?;
+        }
+        if !plan.stale.is_empty() {
+            self.delete(&plan.stale).await?;
+        }
+
+        Ok(plan.uuids(inputs, &added_uuids))
+    }
+
+    // The k documents matching the filter most similar to the query with their similarity,
+    // the most similar first.
+    async fn similarity_search_with_filter(
+        &self,
+        query: &str,
+        k: usize,
+        filter: &Filter,
+    ) -> Result<Vec<(Document, f32)>>;
+
+    async fn similarity_search_with_score(
+        &self,
+        query: &str,
+        k: usize,
+    ) -> Result<Vec<(Document, f32)>> {
+        self.similarity_search_with_filter(query, k, &Filter::default())
+            .await
+    }
+
+    async fn similarity_search(&self, query: &str, k: usize) -> Result<Vec<Document>> {
+        let documents = self.similarity_search_with_score(query, k).await?;
+        Ok(documents.into_iter().map(|x| x.0).collect())
+    }
+}
+
+// A document as the upsert sees it: its path and content md5. The metadata, such as the
+// modified time or the line numbers, changes without the content and isn't worth an embedding.
+type DocumentId = (String, String);
+
+fn document_id(document: &Document) -> DocumentId {
+    (document.path.clone(), document.content_md5.clone())
+}
+
+// What upserting the inputs does to the stored documents of their paths.
+pub(crate) struct UpsertPlan {
+    // The uuid of the inputs stored already.
+    stored: HashMap<DocumentId, String>,
+    // The inputs to embed and add, each once even if given twice.
+    pub(crate) added: Documents,
+    added_ids: HashMap<DocumentId, usize>,
+    // The stored inputs whose metadata changed, with their new metadata.
+    pub(crate) updated: Vec<(String, Metadata)>,
+    // The other documents of the paths and the duplicates.
+    pub(crate) stale: Vec<String>,
+}
+
+impl UpsertPlan {
+    // The paths of the inputs, to get their stored keys.
+    pub(crate) fn paths(inputs: &Documents) -> Vec<String> {
+        inputs
+            .iter()
+            .map(|x| x.path)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    pub(crate) fn create(inputs: &Documents, keys: Vec<DocumentKey>) -> Self {
+        // The metadata of the first input of each id.
+        let mut wanted = HashMap::new();
+        for document in inputs.iter() {
+            wanted
+                .entry(document_id(&document))
+                .or_insert(document.metadata);
+        }
+
+        let mut stored = HashMap::new();
+        let mut updated = vec![];
+        let mut stale = vec![];
+        for key in keys {
+            let id = (key.path, key.content_md5);
+            let Some(metadata) = wanted.get(&id) else {
+                stale.push(key.uuid);
+                continue;
+            };
+            match stored.entry(id) {
+                Entry::Vacant(x) => {
+                    if key.metadata != *metadata {
+                        updated.push((key.uuid.clone(), metadata.clone()));
+                    }
+                    x.insert(key.uuid);
+                }
+                Entry::Occupied(_) => stale.push(key.uuid),
+            }
+        }
+
+        let added = Documents::create();
+        let mut added_ids = HashMap::new();
+        for document in inputs {
+            let id = document_id(&document);
+            if !stored.contains_key(&id) && !added_ids.contains_key(&id) {
+                added_ids.insert(id, added.len());
+                added.push(document);
+            }
+        }
+
+        UpsertPlan {
+            stored,
+            added,
+            added_ids,
+            updated,
+            stale,
+        }
+    }
+
+    // The uuid of each input, given the uuids of the added documents in order.
+    pub(crate) fn uuids(&self, inputs: &Documents, added_uuids: &[String]) -> Vec<String> {
+        inputs
+            .iter()
+            .map(|x| {
+                let id = document_id(&x);
+                match self.stored.get(&id) {
+                    Some(uuid) => uuid.clone(),
+                    None => added_uuids[self.added_ids[&id]].clone(),
+                }
+            })
+            .collect()
+    }
 }
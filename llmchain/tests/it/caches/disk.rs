@@ -0,0 +1,36 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use llmchain::Cache;
+use llmchain::DiskCache;
+
+#[test]
+fn test_disk_cache() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("llmchain-cache-{}", uuid::Uuid::new_v4()));
+    let dir = dir.to_str().unwrap();
+
+    let cache = DiskCache::create(dir)?;
+    assert_eq!(cache.get("a")?, None);
+    cache.set("a", "1")?;
+    cache.set("a", "2")?;
+    assert_eq!(cache.get("a")?, Some("2".to_string()));
+
+    // Survives a new instance.
+    let cache = DiskCache::create(dir)?;
+    assert_eq!(cache.get("a")?, Some("2".to_string()));
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
@@ -0,0 +1,36 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use llmchain::Cache;
+use llmchain::MemoryCache;
+
+#[test]
+fn test_memory_cache() -> Result<()> {
+    let cache = MemoryCache::create(2);
+    assert!(cache.is_empty());
+
+    cache.set("a", "1")?;
+    cache.set("b", "2")?;
+    assert_eq!(cache.get("a")?, Some("1".to_string()));
+
+    // "b" is the least recently used.
+    cache.set("c", "3")?;
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get("b")?, None);
+    assert_eq!(cache.get("a")?, Some("1".to_string()));
+    assert_eq!(cache.get("c")?, Some("3".to_string()));
+
+    Ok(())
+}
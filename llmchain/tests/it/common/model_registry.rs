@@ -0,0 +1,78 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use llmchain::ModelInfo;
+use llmchain::ModelRegistry;
+use llmchain::OpenAIEmbeddingModel;
+use llmchain::OpenAIGenerateModel;
+use llmchain::Tokenizer;
+
+#[test]
+fn test_model_registry() {
+    let registry = ModelRegistry::create();
+
+    let model = registry.get("gpt-4").unwrap();
+    assert_eq!(model.context_length, 8192);
+    assert_eq!(model.tokenizer, Tokenizer::Cl100kBase);
+
+    // Snapshots resolve to the longest registered prefix.
+    let model = registry.get("gpt-4-32k-0613").unwrap();
+    assert_eq!(model.name, "gpt-4-32k-0613");
+    assert_eq!(model.context_length, 32768);
+    let model = registry.get("gpt-4o-mini-2024-07-18").unwrap();
+    assert_eq!(model.tokenizer, Tokenizer::O200kBase);
+    assert_eq!(model.max_output_tokens, 16384);
+    assert_eq!(model.prompt_price, 0.00015);
+
+    let model = registry.get("text-embedding-3-large").unwrap();
+    assert_eq!(model.embedding_dimension, Some(3072));
+
+    // Unknown models.
+    assert!(registry.get("llama2").is_none());
+    let model = registry.get_or_default("llama2");
+    assert_eq!(model.context_length, 4096);
+    assert_eq!(model.max_output_tokens, 4096);
+    assert_eq!(model.cost(1000, 1000), 0.0);
+
+    // Custom models.
+    registry
+        .register(ModelInfo::create("llama2", 4096, Tokenizer::R50kBase).with_prices(0.001, 0.002));
+    let model = registry.get("llama2-13b").unwrap();
+    assert_eq!(model.tokenizer, Tokenizer::R50kBase);
+    assert_eq!(model.cost(2000, 500), 0.003);
+}
+
+#[test]
+fn test_model_registry_openai_models() {
+    let model = OpenAIGenerateModel::Gpt35.info();
+    assert_eq!(model.context_length, 16385);
+    assert_eq!(model.max_output_tokens, 4096);
+    let model = OpenAIGenerateModel::Gpt4o.info();
+    assert_eq!(model.context_length, 128000);
+    assert_eq!(model.max_output_tokens, 16384);
+    assert_eq!(
+        OpenAIGenerateModel::Gpt4o.info().tokenizer,
+        Tokenizer::O200kBase
+    );
+    assert_eq!(
+        OpenAIEmbeddingModel::TextEmbeddingAda002
+            .info()
+            .embedding_dimension,
+        Some(1536)
+    );
+    assert_eq!(
+        OpenAIEmbeddingModel::TextEmbedding3Small.to_string(),
+        "text-embedding-3-small"
+    );
+}
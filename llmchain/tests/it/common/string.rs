@@ -13,6 +13,8 @@
 // limitations under the License.
 
 use llmchain::escape_sql_string;
+use llmchain::quote_sql_string;
+use llmchain::validate_sql_identifier;
 
 #[test]
 fn test_escape_sql_string() {
@@ -24,11 +26,50 @@ fn test_escape_sql_string() {
     let output = escape_sql_string(input);
     assert_eq!(output, "Hello, ''World''!");
 
+    // The newlines are kept, not turned into spaces.
     let input = "Hello, 'World'! \n";
     let output = escape_sql_string(input);
-    assert_eq!(output, "Hello, ''World''!  ");
+    assert_eq!(output, r"Hello, ''World''! \n");
 
     let input = "Hello, 'World'! \r";
     let output = escape_sql_string(input);
     assert_eq!(output, "Hello, ''World''! \\r");
 }
+
+#[test]
+fn test_quote_sql_string() {
+    assert_eq!(quote_sql_string(""), "''");
+    assert_eq!(quote_sql_string("it's"), "'it''s'");
+    assert_eq!(
+        quote_sql_string("# Title\n\n\tcode\r\n"),
+        r"'# Title\n\n\tcode\r\n'"
+    );
+    assert_eq!(quote_sql_string(r"C:\dir\n"), r"'C:\\dir\\n'");
+    assert_eq!(quote_sql_string("a\0b"), r"'a\0b'");
+    assert_eq!(quote_sql_string("数据库 ✓"), "'数据库 ✓'");
+
+    // Nothing closes the literal early.
+    let quoted = quote_sql_string(r"x'); DROP TABLE t; --\'");
+    assert_eq!(quoted, r"'x''); DROP TABLE t; --\\'''");
+    let inner = &quoted[1..quoted.len() - 1];
+    assert!(!inner.replace(r"\\", "").replace("''", "").contains('\''));
+}
+
+#[test]
+fn test_validate_sql_identifier() {
+    for name in ["embedding_store", "llmchain_collection", "_t1", "T"] {
+        assert!(validate_sql_identifier(name).is_ok(), "{}", name);
+    }
+    for name in [
+        "",
+        "1t",
+        "a.b",
+        "a b",
+        "t; DROP TABLE x",
+        "`t`",
+        "t-1",
+        "表",
+    ] {
+        assert!(validate_sql_identifier(name).is_err(), "{}", name);
+    }
+}
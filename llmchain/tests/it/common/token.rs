@@ -13,10 +13,49 @@
 // limitations under the License.
 
 use llmchain::chat_tokens;
+use llmchain::completion_budget;
+use llmchain::Document;
+use llmchain::Tokenizer;
 
 #[test]
 fn test_token() {
     let input = "🍌This is a sentence   with spaces, hahhahah haha ha";
     let output = chat_tokens(input).unwrap();
     assert_eq!(output.len(), 17);
+    let document = Document::create("1.md", input);
+    assert_eq!(document.tokens(), 17);
+    assert_eq!(document.model_tokens("gpt-4"), 18);
+
+    assert_eq!(Tokenizer::Cl100kBase.count(input), 18);
+    assert_eq!(Tokenizer::R50kBase.count(input), 17);
+    assert_eq!(
+        Tokenizer::O200kBase
+            .split("hello llmchain")
+            .unwrap()
+            .concat(),
+        "hello llmchain"
+    );
+}
+
+#[test]
+fn test_completion_budget() {
+    // The completion gets what the prompt leaves.
+    let budget = completion_budget(1000, 4096, 4096, 256).unwrap();
+    assert_eq!(budget, 3096);
+
+    // Capped to what the model generates in one answer.
+    let budget = completion_budget(10, 128000, 16384, 256).unwrap();
+    assert_eq!(budget, 16384);
+
+    // Capped to what the request can carry.
+    let budget = completion_budget(10, 200000, 200000, 256).unwrap();
+    assert_eq!(budget, u16::MAX);
+
+    // The prompt alone exceeds the window.
+    let result = completion_budget(5000, 4096, 4096, 256);
+    assert!(result.unwrap_err().to_string().contains("exceeds"));
+
+    // The prompt doesn't leave the reserved tokens.
+    let result = completion_budget(4000, 4096, 4096, 256);
+    assert!(result.unwrap_err().to_string().contains("reserved"));
 }
@@ -0,0 +1,143 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use llmchain::BatchedEmbedding;
+use llmchain::Document;
+use llmchain::Documents;
+use llmchain::Embedding;
+use parking_lot::Mutex;
+
+// Records the batches, the first ones are the slowest to complete.
+#[derive(Default)]
+struct RecordingEmbedding {
+    batches: Mutex<Vec<usize>>,
+    running: AtomicUsize,
+    max_running: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl Embedding for RecordingEmbedding {
+    async fn embed_query(&self, input: &str) -> Result<Vec<f32>> {
+        Ok(vec![input.len() as f32])
+    }
+
+    async fn embed_documents(&self, inputs: &Documents) -> Result<Vec<Vec<f32>>> {
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        let delay = {
+            let mut batches = self.batches.lock();
+            batches.push(inputs.len());
+            100 - batches.len() as u64
+        };
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+
+        Ok(inputs
+            .iter()
+            .map(|x| vec![x.content.parse::<f32>().unwrap()])
+            .collect())
+    }
+}
+
+fn documents(n: usize) -> Documents {
+    Documents::from(
+        (0..n)
+            .map(|i| Document::create(&format!("{}.md", i), &i.to_string()))
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_batched_embedding_by_count() -> Result<()> {
+    let recording = Arc::new(RecordingEmbedding::default());
+    let progress = Arc::new(Mutex::new(vec![]));
+    let progress_clone = progress.clone();
+    let embedding = BatchedEmbedding::create(recording.clone())
+        .with_batch_size(3)
+        .with_concurrency(2)
+        .with_progress(move |done, total| progress_clone.lock().push((done, total)));
+
+    let embeddings = embedding.embed_documents(&documents(10)).await?;
+    let expected = (0..10).map(|i| vec![i as f32]).collect::<Vec<_>>();
+    assert_eq!(embeddings, expected);
+
+    assert_eq!(*recording.batches.lock(), vec![3, 3, 3, 1]);
+    assert_eq!(recording.max_running.load(Ordering::SeqCst), 2);
+
+    let progress = progress.lock();
+    assert_eq!(progress.len(), 4);
+    assert_eq!(progress.last(), Some(&(10, 10)));
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_batched_embedding_by_tokens() -> Result<()> {
+    let recording = Arc::new(RecordingEmbedding::default());
+    let embedding = BatchedEmbedding::create(recording.clone())
+        .with_batch_size(100)
+        .with_batch_tokens(2);
+
+    // Every document is a single token.
+    let embeddings = embedding.embed_documents(&documents(5)).await?;
+    assert_eq!(embeddings.len(), 5);
+    assert_eq!(*recording.batches.lock(), vec![2, 2, 1]);
+
+    let embeddings = embedding.embed_documents(&Documents::create()).await?;
+    assert!(embeddings.is_empty());
+
+    Ok(())
+}
+
+// The first batch fails at once, the others take a while.
+#[derive(Default)]
+struct FailingEmbedding {
+    started: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl Embedding for FailingEmbedding {
+    async fn embed_query(&self, input: &str) -> Result<Vec<f32>> {
+        Ok(vec![input.len() as f32])
+    }
+
+    async fn embed_documents(&self, inputs: &Documents) -> Result<Vec<Vec<f32>>> {
+        if self.started.fetch_add(1, Ordering::SeqCst) == 0 {
+            anyhow::bail!("rate limited");
+        }
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        Ok(inputs.iter().map(|_| vec![0.0]).collect())
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_batched_embedding_failure() -> Result<()> {
+    let failing = Arc::new(FailingEmbedding::default());
+    let embedding = BatchedEmbedding::create(failing.clone())
+        .with_batch_size(1)
+        .with_concurrency(2);
+
+    let error = embedding.embed_documents(&documents(10)).await.unwrap_err();
+    assert_eq!(error.to_string(), "rate limited");
+    // No batch is started after the failure.
+    assert!(failing.started.load(Ordering::SeqCst) <= 2);
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use anyhow::Result;
+use llmchain::CachedEmbedding;
+use llmchain::DiskCache;
+use llmchain::Document;
+use llmchain::Documents;
+use llmchain::Embedding;
+use llmchain::HashingEmbedding;
+use llmchain::MemoryCache;
+
+// Counts the documents which reach it.
+#[derive(Default)]
+struct CountingEmbedding {
+    embedded: AtomicUsize,
+}
+
+// Drops the last document, and checks the metadata reaches it.
+struct ShortEmbedding;
+
+#[async_trait::async_trait]
+impl Embedding for ShortEmbedding {
+    async fn embed_query(&self, input: &str) -> Result<Vec<f32>> {
+        Ok(vec![input.len() as f32])
+    }
+
+    async fn embed_documents(&self, inputs: &Documents) -> Result<Vec<Vec<f32>>> {
+        assert!(inputs.iter().all(|x| x.metadata.contains_key("source")));
+        Ok(inputs
+            .iter()
+            .skip(1)
+            .map(|x| vec![x.content.len() as f32])
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl Embedding for CountingEmbedding {
+    async fn embed_query(&self, input: &str) -> Result<Vec<f32>> {
+        self.embedded.fetch_add(1, Ordering::SeqCst);
+        Ok(vec![input.len() as f32])
+    }
+
+    async fn embed_documents(&self, inputs: &Documents) -> Result<Vec<Vec<f32>>> {
+        self.embedded.fetch_add(inputs.len(), Ordering::SeqCst);
+        Ok(inputs
+            .iter()
+            .map(|x| vec![x.content.len() as f32])
+            .collect())
+    }
+}
+
+#[tokio::test]
+async fn test_cached_embedding() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("llmchain-cache-{}", uuid::Uuid::new_v4()));
+    let dir = dir.to_str().unwrap();
+    let counting = Arc::new(CountingEmbedding::default());
+
+    let embedding = CachedEmbedding::create(counting.clone(), DiskCache::create(dir)?);
+    let documents = Documents::from(vec![
+        Document::create("a.md", "a"),
+        Document::create("b.md", "bb"),
+    ]);
+    let embeddings = embedding.embed_documents(&documents).await?;
+    assert_eq!(embeddings, vec![vec![1.0], vec![2.0]]);
+    assert_eq!(counting.embedded.load(Ordering::SeqCst), 2);
+
+    // A new run over the same directory, only the changed document is embedded.
+    let embedding = CachedEmbedding::create(counting.clone(), DiskCache::create(dir)?);
+    let documents = Documents::from(vec![
+        Document::create("a.md", "a"),
+        Document::create("b.md", "bbb"),
+    ]);
+    let embeddings = embedding.embed_documents(&documents).await?;
+    assert_eq!(embeddings, vec![vec![1.0], vec![3.0]]);
+    assert_eq!(counting.embedded.load(Ordering::SeqCst), 3);
+
+    // A query shares the entry of a document with the same content.
+    assert_eq!(embedding.embed_query("bbb").await?, vec![3.0]);
+    assert_eq!(counting.embedded.load(Ordering::SeqCst), 3);
+
+    std::fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cached_embedding_models() -> Result<()> {
+    let cache = MemoryCache::create(100);
+    let small = CachedEmbedding::create(HashingEmbedding::create(8), cache.clone());
+    let large = CachedEmbedding::create(HashingEmbedding::create(16), cache.clone());
+    assert_ne!(small.model(), large.model());
+
+    // The vectors of another model sharing the cache are not returned.
+    assert_eq!(small.embed_query("hello").await?.len(), 8);
+    assert_eq!(large.embed_query("hello").await?.len(), 16);
+
+    // Nor the ones before the weights were fitted.
+    let hashing = HashingEmbedding::create(8);
+    let fitted = CachedEmbedding::create(
+        hashing.with_idf(&Documents::from(vec![Document::create(
+            "a.md",
+            "hello world",
+        )])),
+        cache,
+    );
+    assert_ne!(fitted.model(), small.model());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cached_embedding_short_answer() -> Result<()> {
+    let cache = MemoryCache::create(100);
+    let embedding = CachedEmbedding::create(Arc::new(ShortEmbedding), cache.clone());
+    let documents = Documents::from(vec![
+        Document::create("a.md", "a").with_metadata("source", "a.md"),
+        Document::create("b.md", "bb").with_metadata("source", "b.md"),
+    ]);
+    let error = embedding.embed_documents(&documents).await.unwrap_err();
+    assert_eq!(error.to_string(), "got 1 embeddings for 2 documents");
+
+    // Nothing was cached, a working embedding answers next.
+    let counting = Arc::new(CountingEmbedding::default());
+    let embedding = CachedEmbedding::create(counting.clone(), cache);
+    assert_eq!(embedding.embed_documents(&documents).await?, vec![
+        vec![1.0],
+        vec![2.0]
+    ]);
+    assert_eq!(counting.embedded.load(Ordering::SeqCst), 2);
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use llmchain::Document;
+use llmchain::Documents;
+use llmchain::Embedding;
+use llmchain::FakeEmbedding;
+
+#[tokio::test]
+async fn test_fake_embedding() -> Result<()> {
+    let embedding = FakeEmbedding::create(32);
+
+    let hello = embedding.embed_query("hello").await?;
+    assert_eq!(hello.len(), 32);
+    assert!(hello.iter().all(|x| (-1.0..=1.0).contains(x)));
+    let norm = hello.iter().map(|x| x * x).sum::<f32>().sqrt();
+    assert!((norm - 1.0).abs() < 1e-5);
+
+    // Deterministic.
+    assert_eq!(embedding.embed_query("hello").await?, hello);
+    assert_ne!(embedding.embed_query("world").await?, hello);
+
+    let documents = Documents::from(vec![
+        Document::create("a.md", "hello"),
+        Document::create("b.md", "world"),
+    ]);
+    let embeddings = embedding.embed_documents(&documents).await?;
+    assert_eq!(embeddings.len(), 2);
+    assert_eq!(embeddings[0], hello);
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use llmchain::Document;
+use llmchain::Documents;
+use llmchain::Embedding;
+use llmchain::HashingEmbedding;
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[tokio::test]
+async fn test_hashing_embedding() -> Result<()> {
+    let embedding = HashingEmbedding::create(256);
+
+    let query = embedding.embed_query("How to create a table?").await?;
+    assert_eq!(query.len(), 256);
+    let norm = query.iter().map(|x| x * x).sum::<f32>().sqrt();
+    assert!((norm - 1.0).abs() < 1e-5);
+
+    // Deterministic, and the case or the punctuation don't matter.
+    assert_eq!(embedding.embed_query("how to CREATE a table").await?, query);
+
+    let documents = Documents::from(vec![
+        Document::create("a.md", "CREATE TABLE creates a new table in the database."),
+        Document::create("b.md", "The weather is sunny at the beach today."),
+    ]);
+    let embeddings = embedding.embed_documents(&documents).await?;
+    assert_eq!(embeddings.len(), 2);
+    assert!(cosine(&query, &embeddings[0]) > cosine(&query, &embeddings[1]));
+
+    // Nothing to hash.
+    let empty = embedding.embed_query("...").await?;
+    assert!(empty.iter().all(|x| *x == 0.0));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_hashing_embedding_idf() -> Result<()> {
+    let documents = Documents::from(vec![
+        Document::create("a.md", "databend query engine"),
+        Document::create("b.md", "databend storage engine"),
+        Document::create("c.md", "databend cloud warehouse"),
+    ]);
+    let embedding = HashingEmbedding::create(1024)
+        .with_bigrams(false)
+        .with_idf(&documents);
+    let embeddings = embedding.embed_documents(&documents).await?;
+
+    // "databend" is in every document, "query" is only in the first one.
+    let query = embedding.embed_query("databend query").await?;
+    let scores = embeddings
+        .iter()
+        .map(|x| cosine(&query, x))
+        .collect::<Vec<_>>();
+    assert!(scores[0] > scores[1]);
+    assert!(scores[0] > scores[2]);
+
+    let common = embedding.embed_query("databend").await?;
+    let rare = embedding.embed_query("query").await?;
+    assert!(cosine(&common, &embeddings[0]) < cosine(&rare, &embeddings[0]));
+
+    Ok(())
+}
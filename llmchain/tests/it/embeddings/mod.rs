@@ -12,5 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod batched;
+mod cached;
 mod databend;
+mod fake;
+mod hashing;
 mod openai;
@@ -0,0 +1,160 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use anyhow::Result;
+use llmchain::DirectoryLoader;
+use llmchain::Documents;
+use llmchain::Embedding;
+use llmchain::HashingEmbedding;
+use llmchain::InMemoryVectorStore;
+use llmchain::IndexManifest;
+use llmchain::Indexer;
+use llmchain::LocalDisk;
+use llmchain::TextLoader;
+use llmchain::TextSplitter;
+use llmchain::VectorStore;
+
+#[tokio::test]
+async fn test_indexer() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("llmchain-indexer-{}", uuid::Uuid::new_v4()));
+    let docs = dir.join("docs");
+    fs::create_dir_all(docs.join("guide"))?;
+    fs::write(docs.join("a.txt"), "alpha\nbeta")?;
+    fs::write(docs.join("guide/b.txt"), "gamma\ndelta")?;
+    fs::write(docs.join("c.txt"), "epsilon")?;
+    let docs = format!("{}/", docs.to_str().unwrap());
+    let manifest = dir.join("manifest.json");
+    let manifest = manifest.to_str().unwrap();
+
+    let loader = DirectoryLoader::create(LocalDisk::create()?)
+        .with_loader("**/*.txt", TextLoader::create(LocalDisk::create()?));
+    let embedding = Arc::new(CountingEmbedding {
+        embedding: HashingEmbedding::create(256),
+        embedded: AtomicUsize::new(0),
+    });
+    let store = Arc::new(InMemoryVectorStore::create(embedding.clone()).with_min_similarity(0.0));
+    let splitter = TextSplitter::create()
+        .with_chunk_size(1)
+        .with_separators(vec!["\n".to_string()]);
+    let indexer = Indexer::create(Arc::new(loader), store.clone(), manifest)
+        .with_splitter(Arc::new(splitter));
+
+    // The dry run writes nothing.
+    let report = indexer.plan(&docs).await?;
+    assert!(report.dry_run);
+    assert_eq!(report.added.len(), 3);
+    assert_eq!(report.chunks_added, 5);
+    assert!(store.is_empty());
+    assert!(!dir.join("manifest.json").exists());
+
+    let report = indexer.index(&docs).await?;
+    assert_eq!(report.added.len(), 3);
+    assert_eq!(report.chunks_added, 5);
+    assert_eq!(store.len(), 5);
+    assert_eq!(IndexManifest::load(manifest)?.chunk_count(), 5);
+
+    // Nothing changed.
+    let report = indexer.index(&docs).await?;
+    assert!(report.is_empty());
+    assert_eq!(report.unchanged, 3);
+    assert_eq!(report.chunks_unchanged, 5);
+
+    // One line changed, one file deleted, one added. Only the new lines are embedded,
+    // the modified time only updates the metadata of the other chunks.
+    fs::write(format!("{}a.txt", docs), "alpha\nbeta 2")?;
+    fs::remove_file(format!("{}c.txt", docs))?;
+    fs::write(format!("{}d.txt", docs), "zeta")?;
+    let plan = indexer.plan(&docs).await?;
+    assert_eq!(plan.added, vec![format!("{}d.txt", docs)]);
+    assert_eq!(plan.changed, vec![format!("{}a.txt", docs)]);
+    assert_eq!(plan.deleted, vec![format!("{}c.txt", docs)]);
+    assert_eq!(plan.unchanged, 1);
+    assert_eq!((plan.chunks_added, plan.chunks_deleted), (2, 2));
+    assert_eq!(plan.chunks_updated + plan.chunks_unchanged, 3);
+    assert!(plan.to_string().contains(&format!("~ {}a.txt", docs)));
+
+    let embedded = embedding.embedded.load(Ordering::SeqCst);
+    let report = indexer.index(&docs).await?;
+    assert_eq!(report.added, plan.added);
+    assert_eq!(report.chunks_added, plan.chunks_added);
+    assert!(!report.dry_run);
+    assert_eq!(store.len(), 5);
+    assert_eq!(embedding.embedded.load(Ordering::SeqCst) - embedded, 2);
+
+    let manifest_file = manifest;
+    let manifest = IndexManifest::load(manifest_file)?;
+    assert_eq!(manifest.files.len(), 3);
+    assert_eq!(manifest.chunk_count(), 5);
+    let similarities = store.similarity_search("beta 2", 1).await?;
+    assert_eq!(similarities[0].content.trim(), "beta 2");
+    let similarities = store.similarity_search("epsilon", 5).await?;
+    assert!(similarities.iter().all(|x| x.content.trim() != "epsilon"));
+
+    // The lines moved, the chunks get their new line numbers without an embedding.
+    fs::write(format!("{}guide/b.txt", docs), "omega\ngamma\ndelta")?;
+    let embedded = embedding.embedded.load(Ordering::SeqCst);
+    let report = indexer.index(&docs).await?;
+    assert_eq!(
+        (
+            report.chunks_added,
+            report.chunks_updated,
+            report.chunks_deleted,
+            report.chunks_unchanged
+        ),
+        (1, 2, 0, 3)
+    );
+    assert_eq!(embedding.embedded.load(Ordering::SeqCst) - embedded, 1);
+    assert_eq!(store.len(), 6);
+    let similarities = store.similarity_search("gamma", 1).await?;
+    assert_eq!(similarities[0].content.trim(), "gamma");
+    assert_eq!(similarities[0].metadata["line_start"].as_i64(), Some(2));
+
+    // The emptied file has no chunks left.
+    let file = format!("{}d.txt", docs);
+    fs::write(&file, "")?;
+    let report = indexer.index(&docs).await?;
+    assert_eq!(report.changed, vec![file.clone()]);
+    assert_eq!((report.chunks_added, report.chunks_deleted), (0, 1));
+    assert!(store.document_keys(&report.changed).await?.is_empty());
+    assert_eq!(store.len(), 5);
+    assert!(IndexManifest::load(manifest_file)?.files[&file]
+        .chunks
+        .is_empty());
+
+    fs::remove_dir_all(dir)?;
+    Ok(())
+}
+
+// Counts the documents which reach the embedding.
+struct CountingEmbedding {
+    embedding: Arc<HashingEmbedding>,
+    embedded: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl Embedding for CountingEmbedding {
+    async fn embed_query(&self, input: &str) -> Result<Vec<f32>> {
+        self.embedding.embed_query(input).await
+    }
+
+    async fn embed_documents(&self, inputs: &Documents) -> Result<Vec<Vec<f32>>> {
+        self.embedded.fetch_add(inputs.len(), Ordering::SeqCst);
+        self.embedding.embed_documents(inputs).await
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use futures::StreamExt;
+use llmchain::Anthropic;
+use llmchain::AnthropicModel;
+use llmchain::ChatMessage;
+use llmchain::GenerateChunk;
+use llmchain::GenerateOptions;
+use llmchain::HttpError;
+use llmchain::UsageKind;
+use llmchain::UsageTracker;
+use llmchain::LLM;
+use serde_json::json;
+use wiremock::matchers::body_partial_json;
+use wiremock::matchers::header;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+
+#[tokio::test]
+async fn test_llm_anthropic_chat() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(header("x-api-key", "sk-ant-test"))
+        .and(header("anthropic-version", "2023-06-01"))
+        .and(body_partial_json(json!({
+            "model": "claude-3-haiku-20240307",
+            "max_tokens": 100,
+            "system": "You are a helpful assistant.",
+            "messages": [
+                {"role": "user", "content": "say Hello"},
+                {"role": "assistant", "content": "Hello"},
+                {"role": "user", "content": "again"}
+            ],
+            "stop_sequences": ["\n"],
+            "stream": false
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-3-haiku-20240307",
+            "content": [{"type": "text", "text": "Hello again"}],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 20, "output_tokens": 3}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let tracker = UsageTracker::create();
+    let llm = Anthropic::create("sk-ant-test")
+        .with_api_base(&server.uri())
+        .with_generate_model(AnthropicModel::Claude3Haiku)
+        .with_usage_tracker(tracker.clone());
+    let options = GenerateOptions::create()
+        .with_max_tokens(100)
+        .with_stop(vec!["\n"]);
+    let result = llm
+        .chat(
+            vec![
+                ChatMessage::system("You are a helpful assistant."),
+                ChatMessage::user("say Hello"),
+                ChatMessage::assistant("Hello"),
+                ChatMessage::user("again"),
+            ],
+            &options,
+        )
+        .await?;
+    assert_eq!(result.generation, "Hello again");
+    assert_eq!(result.prompt_tokens, 20);
+    assert_eq!(result.completion_tokens, 3);
+    assert_eq!(result.total_tokens, 23);
+    assert_eq!(result.provider, "Anthropic");
+
+    let usage = tracker.usage("claude-3-haiku-20240307", UsageKind::Generation);
+    assert_eq!(usage.total_tokens, 23);
+    assert!(usage.cost > 0.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_anthropic_seed_ignored() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-3-5-sonnet-20241022",
+            "content": [{"type": "text", "text": "Hello"}],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 10, "output_tokens": 1}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let llm = Anthropic::create("sk-ant-test").with_api_base(&server.uri());
+    let options = GenerateOptions::create().with_seed(42);
+    let result = llm
+        .chat(vec![ChatMessage::user("say Hello")], &options)
+        .await?;
+    assert_eq!(result.generation, "Hello");
+
+    // The Messages API has no seed field.
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&requests[0].body)?;
+    assert!(body.get("seed").is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_anthropic_stream() -> Result<()> {
+    let events = [
+        r#"{"type":"message_start","message":{"id":"msg_1","type":"message","role":"assistant","content":[],"model":"claude-3-5-sonnet-20241022","usage":{"input_tokens":10,"output_tokens":1}}}"#,
+        r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+        r#"{"type":"ping"}"#,
+        r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hello"}}"#,
+        r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":" world"}}"#,
+        r#"{"type":"content_block_stop","index":0}"#,
+        r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":2}}"#,
+        r#"{"type":"message_stop"}"#,
+    ];
+    let body = events
+        .iter()
+        .map(|data| {
+            let event = serde_json::from_str::<serde_json::Value>(data).unwrap()["type"]
+                .as_str()
+                .unwrap()
+                .to_string();
+            format!("event: {}\ndata: {}\n\n", event, data)
+        })
+        .collect::<String>();
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(body_partial_json(json!({"stream": true})))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+        .mount(&server)
+        .await;
+
+    let llm = Anthropic::create("sk-ant-test").with_api_base(&server.uri());
+    let chunks = llm
+        .generate_stream("say Hello world")
+        .await?
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(chunks, vec![
+        GenerateChunk::Delta("Hello".to_string()),
+        GenerateChunk::Delta(" world".to_string()),
+        GenerateChunk::Usage {
+            prompt_tokens: 10,
+            completion_tokens: 2,
+            total_tokens: 12
+        },
+    ]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_anthropic_errors() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(body_partial_json(json!({"stream": false})))
+        .respond_with(ResponseTemplate::new(500).set_body_json(json!({
+            "type": "error",
+            "error": {"type": "api_error", "message": "Internal server error"}
+        })))
+        .mount(&server)
+        .await;
+    let body = "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n";
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(body_partial_json(json!({"stream": true})))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+        .mount(&server)
+        .await;
+
+    let llm = Anthropic::create("sk-ant-test").with_api_base(&server.uri());
+    let err = llm.generate("say Hello").await.unwrap_err();
+    let err = err.downcast_ref::<HttpError>().unwrap();
+    assert_eq!(err.status, 500);
+    assert_eq!(err.message, "Internal server error");
+    assert!(err.is_transient());
+
+    let mut stream = llm.generate_stream("say Hello").await?;
+    let err = stream.next().await.unwrap().unwrap_err();
+    assert_eq!(err.downcast_ref::<HttpError>().unwrap().status, 529);
+
+    assert!(llm.embedding(vec!["hello".to_string()]).await.is_err());
+
+    Ok(())
+}
+
+#[ignore]
+#[tokio::test]
+async fn test_llm_anthropic_generate() -> Result<()> {
+    let api_key = std::env::var("ANTHROPIC_API_KEY").unwrap_or("".to_string());
+
+    let llm = Anthropic::create(&api_key).with_generate_model(AnthropicModel::Claude3Haiku);
+    let result = llm.generate("say Hello").await?;
+    assert!(result.generation.contains("Hello"));
+    assert!(result.total_tokens > 0);
+
+    Ok(())
+}
@@ -12,9 +12,117 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::time::Duration;
+
 use anyhow::Result;
 use llmchain::AzureOpenAI;
+use llmchain::ChatMessage;
+use llmchain::GenerateOptions;
 use llmchain::LLM;
+use serde_json::json;
+use wiremock::matchers::body_partial_json;
+use wiremock::matchers::header;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::matchers::query_param;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+
+use crate::llms::cassette::Cassette;
+
+// Replays tests/testdata/cassettes/azure_openai.json, a synthetic cassette recorded against a mock upstream
+// with hand-written answers, set LLMCHAIN_RECORD and the AZURE_OPENAI_* variables to record the real API.
+#[tokio::test]
+async fn test_llm_azure_openai_cassette() -> Result<()> {
+    let api_base = std::env::var("AZURE_OPENAI_API_BASE").unwrap_or("".to_string());
+    let api_key = std::env::var("AZURE_OPENAI_API_KEY").unwrap_or("".to_string());
+    let gen_deployment =
+        std::env::var("AZURE_OPENAI_API_GEN_DEPLOYMENT").unwrap_or("gpt-35-turbo".to_string());
+    let embed_deployment = std::env::var("AZURE_OPENAI_API_EMBED_DEPLOYMENT")
+        .unwrap_or("text-embedding-ada-002".to_string());
+    let cassette =
+        Cassette::create("tests/testdata/cassettes/azure_openai.json", &api_base).await?;
+    let http_client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()?;
+
+    let llm = AzureOpenAI::create(&cassette.url(), &api_key, &gen_deployment)
+        .with_http_client(http_client.clone());
+    let result = llm.generate("say Hello").await?;
+    assert!(result.generation.contains("Hello"));
+    assert_eq!(result.prompt_tokens, 10);
+    assert_eq!(result.completion_tokens, 9);
+    assert_eq!(result.total_tokens, 19);
+
+    let llm = AzureOpenAI::create(&cassette.url(), &api_key, &embed_deployment)
+        .with_http_client(http_client);
+    let result = llm.embedding(vec!["llmchain".to_string()]).await?;
+    assert_eq!(result.embeddings.len(), 1);
+    assert_eq!(result.prompt_tokens, 3);
+    assert_eq!(result.total_tokens, 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_azure_openai_embedding_order() -> Result<()> {
+    let server = MockServer::start().await;
+    // Not in the order of the inputs.
+    Mock::given(method("POST"))
+        .and(path("/openai/deployments/ada/embeddings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "object": "list",
+            "model": "text-embedding-ada-002",
+            "data": [
+                {"object": "embedding", "index": 1, "embedding": [0.3, 0.4]},
+                {"object": "embedding", "index": 0, "embedding": [0.1, 0.2]}
+            ],
+            "usage": {"prompt_tokens": 2, "total_tokens": 2}
+        })))
+        .mount(&server)
+        .await;
+
+    let llm = AzureOpenAI::create(&server.uri(), "key", "ada");
+    let result = llm
+        .embedding(vec!["hello".to_string(), "world".to_string()])
+        .await?;
+    assert_eq!(result.embeddings, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_azure_openai_seed() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/openai/deployments/gpt/chat/completions"))
+        .and(query_param("api-version", "2023-03-15-preview"))
+        .and(header("api-key", "key"))
+        .and(body_partial_json(json!({"seed": 42})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-35-turbo",
+            "choices": [
+                {"index": 0, "message": {"role": "assistant", "content": "Hello"}, "finish_reason": "stop"}
+            ],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 1, "total_tokens": 11}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let llm = AzureOpenAI::create(&server.uri(), "key", "gpt");
+    let options = GenerateOptions::create().with_seed(42);
+    let result = llm
+        .chat(vec![ChatMessage::user("say Hello")], &options)
+        .await?;
+    assert_eq!(result.generation, "Hello");
+
+    Ok(())
+}
 
 #[ignore]
 #[tokio::test]
@@ -0,0 +1,235 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures::StreamExt;
+use llmchain::CachedLLM;
+use llmchain::ChatMessage;
+use llmchain::EmbeddingResult;
+use llmchain::GenerateChunk;
+use llmchain::GenerateOptions;
+use llmchain::GenerateResult;
+use llmchain::MemoryCache;
+use llmchain::LLM;
+
+// Counts the calls and the inputs which reach it.
+#[derive(Default)]
+struct CountingLLM {
+    model: String,
+    calls: AtomicUsize,
+    inputs: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl LLM for CountingLLM {
+    fn generate_model(&self) -> String {
+        self.model.clone()
+    }
+
+    fn embedding_model(&self) -> String {
+        self.model.clone()
+    }
+
+    async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.inputs.fetch_add(inputs.len(), Ordering::SeqCst);
+        Ok(EmbeddingResult {
+            prompt_tokens: inputs.len() as u32,
+            total_tokens: inputs.len() as u32,
+            embeddings: inputs.iter().map(|x| vec![x.len() as f32]).collect(),
+            ..Default::default()
+        })
+    }
+
+    async fn chat(
+        &self,
+        messages: Vec<ChatMessage>,
+        options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        let calls = self.calls.fetch_add(1, Ordering::SeqCst);
+        let generation = format!(
+            "{} with {:?} #{}",
+            messages[0].content, options.temperature, calls
+        );
+        Ok(GenerateResult {
+            prompt_tokens: 10,
+            completion_tokens: 5,
+            total_tokens: 15,
+            generations: vec![generation.clone()],
+            generation,
+            ..Default::default()
+        })
+    }
+}
+
+// Answers one embedding less than asked.
+struct ShortLLM;
+
+#[async_trait::async_trait]
+impl LLM for ShortLLM {
+    async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+        Ok(EmbeddingResult {
+            embeddings: inputs
+                .iter()
+                .skip(1)
+                .map(|x| vec![x.len() as f32])
+                .collect(),
+            provider: self.name(),
+            ..Default::default()
+        })
+    }
+
+    async fn chat(
+        &self,
+        _messages: Vec<ChatMessage>,
+        _options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        Ok(GenerateResult::default())
+    }
+}
+
+#[tokio::test]
+async fn test_cached_llm_generate() -> Result<()> {
+    let counting = Arc::new(CountingLLM::default());
+    let llm = CachedLLM::create(counting.clone(), MemoryCache::create(100));
+
+    let first = llm.generate("hello").await?;
+    let second = llm.generate("hello").await?;
+    assert_eq!(first.generation, "hello with None #0");
+    assert_eq!(second.generation, first.generation);
+    assert_eq!(second.total_tokens, 15);
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 1);
+
+    // The options are part of the key.
+    let options = GenerateOptions::create().with_temperature(0.0);
+    let result = llm.generate_with_options("hello", &options).await?;
+    assert_eq!(result.generation, "hello with Some(0.0) #1");
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 2);
+
+    // So is the namespace.
+    let other =
+        CachedLLM::create(counting.clone(), MemoryCache::create(100)).with_namespace("gpt-4");
+    other.generate("hello").await?;
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 3);
+
+    // Streams share the entries with the generations.
+    let chunks = llm
+        .generate_stream("hello")
+        .await?
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(
+        chunks[0].as_ref().unwrap(),
+        &GenerateChunk::Delta("hello with None #0".to_string())
+    );
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 3);
+
+    let chunks = llm
+        .generate_stream("hello stream")
+        .await?
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(chunks.len(), 2);
+    let result = llm.generate("hello stream").await?;
+    assert_eq!(result.generation, "hello stream with None #3");
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 4);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cached_llm_embedding() -> Result<()> {
+    let counting = Arc::new(CountingLLM::default());
+    let llm = CachedLLM::create(counting.clone(), MemoryCache::create(100));
+
+    let result = llm
+        .embedding(vec!["a".to_string(), "bb".to_string()])
+        .await?;
+    assert_eq!(result.embeddings, vec![vec![1.0], vec![2.0]]);
+    assert_eq!(result.total_tokens, 2);
+
+    // Only the new input is embedded.
+    let result = llm
+        .embedding(vec!["bb".to_string(), "ccc".to_string(), "a".to_string()])
+        .await?;
+    assert_eq!(result.embeddings, vec![vec![2.0], vec![3.0], vec![1.0]]);
+    assert_eq!(result.total_tokens, 1);
+    assert_eq!(counting.inputs.load(Ordering::SeqCst), 3);
+
+    // All cached, the LLM is not called.
+    let result = llm.embedding(vec!["ccc".to_string()]).await?;
+    assert_eq!(result.embeddings, vec![vec![3.0]]);
+    assert_eq!(result.total_tokens, 0);
+    assert_eq!(counting.calls.load(Ordering::SeqCst), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cached_llm_models() -> Result<()> {
+    let cache = MemoryCache::create(100);
+    let gpt35 = Arc::new(CountingLLM {
+        model: "gpt-3.5-turbo".to_string(),
+        ..Default::default()
+    });
+    let gpt4 = Arc::new(CountingLLM {
+        model: "gpt-4".to_string(),
+        ..Default::default()
+    });
+    let cached_gpt35 = CachedLLM::create(gpt35.clone(), cache.clone());
+    let cached_gpt4 = CachedLLM::create(gpt4.clone(), cache);
+
+    // The same type and the same request, but not the same model.
+    cached_gpt35.generate("hello").await?;
+    cached_gpt4.generate("hello").await?;
+    assert_eq!(gpt35.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(gpt4.calls.load(Ordering::SeqCst), 1);
+
+    cached_gpt35.embedding(vec!["hello".to_string()]).await?;
+    cached_gpt4.embedding(vec!["hello".to_string()]).await?;
+    assert_eq!(gpt35.inputs.load(Ordering::SeqCst), 1);
+    assert_eq!(gpt4.inputs.load(Ordering::SeqCst), 1);
+
+    // Each one still answers from the cache.
+    cached_gpt4.generate("hello").await?;
+    assert_eq!(gpt4.calls.load(Ordering::SeqCst), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cached_llm_embedding_short_answer() -> Result<()> {
+    let llm = CachedLLM::create(Arc::new(ShortLLM), MemoryCache::create(100));
+    let error = llm
+        .embedding(vec!["a".to_string(), "bb".to_string()])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "ShortLLM returned 1 embeddings for 2 inputs"
+    );
+
+    // Nothing was cached, not even an empty vector.
+    let error = llm.embedding(vec!["bb".to_string()]).await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "ShortLLM returned 0 embeddings for 1 inputs"
+    );
+
+    Ok(())
+}
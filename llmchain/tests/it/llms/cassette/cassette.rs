@@ -0,0 +1,100 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use llmchain::OpenAIBuilder;
+use llmchain::LLM;
+use serde_json::json;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+
+use super::Cassette;
+use super::CassetteMode;
+
+#[tokio::test]
+async fn test_cassette_record_and_replay() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-3.5-turbo",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hello"},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 1, "total_tokens": 11}
+        })))
+        // Only the recording reaches the upstream.
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let file =
+        std::env::temp_dir().join(format!("llmchain-cassette-{}.json", uuid::Uuid::new_v4()));
+    let file = file.to_str().unwrap();
+
+    let cassette = Cassette::create_with_mode(file, &server.uri(), CassetteMode::Record).await?;
+    assert_eq!(cassette.mode(), CassetteMode::Record);
+    let llm = OpenAIBuilder::default()
+        .api_base(format!("{}/v1", cassette.url()))
+        .api_key("sk-secret".to_string())
+        .build()?;
+    let result = llm.generate("say Hello").await?;
+    assert_eq!(result.generation, "Hello");
+    assert_eq!(cassette.interactions().len(), 1);
+
+    // The API key is not recorded.
+    let content = std::fs::read_to_string(file)?;
+    assert!(!content.contains("sk-secret"));
+
+    let cassette = Cassette::create_with_mode(file, &server.uri(), CassetteMode::Replay).await?;
+    let llm = OpenAIBuilder::default()
+        .api_base(format!("{}/v1", cassette.url()))
+        .api_key("sk-other".to_string())
+        .build()?;
+    let result = llm.generate("say Hello").await?;
+    assert_eq!(result.generation, "Hello");
+    assert_eq!(result.total_tokens, 11);
+
+    // Every interaction is replayed once.
+    let result = llm.generate("say Hello").await;
+    assert!(result.is_err());
+
+    // The requests not recorded are errors.
+    let result = llm.generate("say Goodbye").await;
+    assert!(result.is_err());
+
+    std::fs::remove_file(file)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cassette_missing_file() -> Result<()> {
+    let result = Cassette::create_with_mode(
+        "tests/testdata/cassettes/not_exists.json",
+        "https://api.openai.com",
+        CassetteMode::Replay,
+    )
+    .await;
+    assert!(result.is_err());
+
+    Ok(())
+}
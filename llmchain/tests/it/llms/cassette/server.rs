@@ -0,0 +1,261 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::convert::Infallible;
+use std::fs;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use hyper::service::make_service_fn;
+use hyper::service::service_fn;
+use hyper::Body;
+use hyper::Request;
+use hyper::Response;
+use hyper::Server;
+use log::info;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+
+// A request and its response, the request headers are not kept, they hold the API keys.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    // The path and the query.
+    pub uri: String,
+    pub request_body: Value,
+    pub status: u16,
+    pub content_type: String,
+    pub response_body: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    // Forward the requests to the upstream API and save the interactions.
+    Record,
+    // Answer the requests with the saved interactions, without any network.
+    Replay,
+}
+
+// Records the HTTP interactions of a provider with its API into a file and replays them.
+// The providers build their requests inside their SDKs and reqwest has no request hooks,
+// so it's a local proxy server and not a client layer, point the api base of the provider at `url()`:
+//
+//   let cassette = Cassette::create("tests/testdata/cassettes/openai.json", "https://api.openai.com").await?;
+//   let llm = OpenAIBuilder::default().api_base(format!("{}/v1", cassette.url()))...
+//
+// The interactions are recorded when LLMCHAIN_RECORD is set, and replayed otherwise.
+pub struct Cassette {
+    path: String,
+    upstream: String,
+    mode: CassetteMode,
+    addr: SocketAddr,
+    interactions: Mutex<Vec<Interaction>>,
+    // The interactions already replayed, each one answers one request.
+    replayed: Mutex<Vec<bool>>,
+    http_client: reqwest::Client,
+}
+
+impl Cassette {
+    pub async fn create(path: &str, upstream: &str) -> Result<Arc<Self>> {
+        let mode = match std::env::var("LLMCHAIN_RECORD") {
+            Ok(_) => CassetteMode::Record,
+            Err(_) => CassetteMode::Replay,
+        };
+        Self::create_with_mode(path, upstream, mode).await
+    }
+
+    pub async fn create_with_mode(
+        path: &str,
+        upstream: &str,
+        mode: CassetteMode,
+    ) -> Result<Arc<Self>> {
+        let interactions: Vec<Interaction> = match mode {
+            CassetteMode::Record => vec![],
+            CassetteMode::Replay => {
+                let content = fs::read_to_string(path).map_err(|e| {
+                    anyhow!(
+                        "cassette {} can't be read: {}, record it with LLMCHAIN_RECORD=1",
+                        path,
+                        e
+                    )
+                })?;
+                serde_json::from_str(&content)?
+            }
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let cassette = Arc::new(Cassette {
+            path: path.to_string(),
+            upstream: upstream.trim_end_matches('/').to_string(),
+            mode,
+            addr,
+            replayed: Mutex::new(vec![false; interactions.len()]),
+            interactions: Mutex::new(interactions),
+            http_client: reqwest::Client::new(),
+        });
+
+        let server_cassette = cassette.clone();
+        let make_service = make_service_fn(move |_| {
+            let cassette = server_cassette.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let cassette = cassette.clone();
+                    async move { Ok::<_, Infallible>(cassette.handle(request).await) }
+                }))
+            }
+        });
+        let server = Server::from_tcp(listener)?.serve(make_service);
+        tokio::spawn(server);
+
+        Ok(cassette)
+    }
+
+    // The base url standing for the upstream.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.interactions.lock().clone()
+    }
+
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        let result = match self.mode {
+            CassetteMode::Record => self.record(request).await,
+            CassetteMode::Replay => self.replay(request).await,
+        };
+
+        let interaction = match result {
+            Ok(interaction) => interaction,
+            // The cassette errors are answered as a status 599, unlike any API error.
+            Err(e) => {
+                return Response::builder()
+                    .status(599)
+                    .body(Body::from(format!("cassette {}: {}", self.path, e)))
+                    .unwrap();
+            }
+        };
+
+        Response::builder()
+            .status(interaction.status)
+            .header("content-type", interaction.content_type)
+            .body(Body::from(interaction.response_body))
+            .unwrap()
+    }
+
+    async fn record(&self, request: Request<Body>) -> Result<Interaction> {
+        let (parts, body) = request.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+        let uri = parts
+            .uri
+            .path_and_query()
+            .map(|x| x.to_string())
+            .unwrap_or_default();
+
+        let mut upstream_request = self
+            .http_client
+            .request(parts.method.clone(), format!("{}{}", self.upstream, uri))
+            .body(body.to_vec());
+        for (name, value) in parts.headers.iter() {
+            if name != hyper::header::HOST && name != hyper::header::CONTENT_LENGTH {
+                upstream_request = upstream_request.header(name, value);
+            }
+        }
+        let response = upstream_request.send().await?;
+        let status = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|x| x.to_str().ok())
+            .unwrap_or("application/json")
+            .to_string();
+        let response_body = response.text().await?;
+
+        let interaction = Interaction {
+            method: parts.method.to_string(),
+            uri,
+            request_body: request_body(&body),
+            status,
+            content_type,
+            response_body,
+        };
+        info!(
+            "cassette {} recorded {} {}",
+            self.path, interaction.method, interaction.uri
+        );
+
+        // Saved at every interaction, the test may end anytime.
+        let mut interactions = self.interactions.lock();
+        interactions.push(interaction.clone());
+        if let Some(dir) = Path::new(&self.path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&*interactions)?)?;
+
+        Ok(interaction)
+    }
+
+    async fn replay(&self, request: Request<Body>) -> Result<Interaction> {
+        let (parts, body) = request.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+        let method = parts.method.to_string();
+        let uri = parts
+            .uri
+            .path_and_query()
+            .map(|x| x.to_string())
+            .unwrap_or_default();
+        let request_body = request_body(&body);
+
+        let interactions = self.interactions.lock();
+        let mut replayed = self.replayed.lock();
+        let index = interactions
+            .iter()
+            .enumerate()
+            .position(|(i, x)| {
+                !replayed[i] && x.method == method && x.uri == uri && x.request_body == request_body
+            })
+            .ok_or_else(|| {
+                anyhow!(
+                    "no recorded interaction for {} {} {}",
+                    method,
+                    uri,
+                    request_body
+                )
+            })?;
+        replayed[index] = true;
+        Ok(interactions[index].clone())
+    }
+}
+
+// JSON bodies are compared as values, the order of their keys doesn't matter.
+fn request_body(body: &[u8]) -> Value {
+    if body.is_empty() {
+        return Value::Null;
+    }
+    serde_json::from_slice(body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).to_string()))
+}
@@ -0,0 +1,41 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use llmchain::ChatMessage;
+use llmchain::ChatRole;
+
+#[test]
+fn test_chat_message_flatten() {
+    // A lone user message is sent as it is.
+    let messages = vec![ChatMessage::user("say Hello")];
+    assert_eq!(ChatMessage::flatten(&messages), "say Hello");
+
+    let messages = vec![
+        ChatMessage::system("You are a helpful assistant."),
+        ChatMessage::user("what is llmchain?"),
+        ChatMessage::assistant("A LLM framework."),
+        ChatMessage::tool("search", "llmchain.rs"),
+        ChatMessage::user("in which language?"),
+    ];
+    assert_eq!(messages[3].role, ChatRole::Tool);
+    assert_eq!(
+        ChatMessage::flatten(&messages),
+        "System: You are a helpful assistant.\n\
+         User: what is llmchain?\n\
+         Assistant: A LLM framework.\n\
+         Tool(search): llmchain.rs\n\
+         User: in which language?\n\
+         Assistant: "
+    );
+}
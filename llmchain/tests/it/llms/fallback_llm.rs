@@ -0,0 +1,156 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::StreamExt;
+use llmchain::ChatMessage;
+use llmchain::EmbeddingResult;
+use llmchain::FallbackLLM;
+use llmchain::GenerateChunk;
+use llmchain::GenerateOptions;
+use llmchain::GenerateResult;
+use llmchain::LLM;
+
+struct FakeLLM {
+    name: String,
+    delay: Option<Duration>,
+    fail: bool,
+}
+
+impl FakeLLM {
+    fn create(name: &str) -> Arc<Self> {
+        Arc::new(FakeLLM {
+            name: name.to_string(),
+            delay: None,
+            fail: false,
+        })
+    }
+
+    fn failing(name: &str) -> Arc<Self> {
+        Arc::new(FakeLLM {
+            name: name.to_string(),
+            delay: None,
+            fail: true,
+        })
+    }
+
+    fn slow(name: &str, delay: Duration) -> Arc<Self> {
+        Arc::new(FakeLLM {
+            name: name.to_string(),
+            delay: Some(delay),
+            fail: false,
+        })
+    }
+
+    async fn call(&self) -> Result<()> {
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        if self.fail {
+            anyhow::bail!("{} is down", self.name);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl LLM for FakeLLM {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+        self.call().await?;
+        Ok(EmbeddingResult {
+            embeddings: inputs.iter().map(|_| vec![1.0]).collect(),
+            ..Default::default()
+        })
+    }
+
+    async fn chat(
+        &self,
+        _messages: Vec<ChatMessage>,
+        _options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        self.call().await?;
+        Ok(GenerateResult {
+            generation: format!("hello from {}", self.name),
+            ..Default::default()
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_fallback_llm() -> Result<()> {
+    let llm = FallbackLLM::create(vec![
+        FakeLLM::failing("azure"),
+        FakeLLM::create("openai"),
+        FakeLLM::create("databend"),
+    ]);
+
+    let result = llm.generate("say hello").await?;
+    assert_eq!(result.generation, "hello from openai");
+    assert_eq!(result.provider, "openai");
+
+    let result = llm.embedding(vec!["hello".to_string()]).await?;
+    assert_eq!(result.embeddings.len(), 1);
+    assert_eq!(result.provider, "openai");
+
+    let mut stream = llm.generate_stream("say hello").await?;
+    assert_eq!(
+        stream.next().await.unwrap()?,
+        GenerateChunk::Delta("hello from openai".to_string())
+    );
+
+    // None of the LLMs supports tools.
+    let err = llm
+        .chat_with_tools(
+            vec![ChatMessage::user("say hello")],
+            vec![],
+            &GenerateOptions::default(),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "all the LLMs failed: [azure: tool calling is not supported by this LLM, openai: tool calling is not supported by this LLM, databend: tool calling is not supported by this LLM]"
+    );
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_fallback_llm_timeout() -> Result<()> {
+    let llm = FallbackLLM::create(vec![
+        FakeLLM::slow("azure", Duration::from_secs(60)),
+        FakeLLM::create("openai"),
+    ])
+    .with_timeout(Duration::from_secs(10));
+
+    let result = llm.generate("say hello").await?;
+    assert_eq!(result.provider, "openai");
+
+    let llm = FallbackLLM::create(vec![FakeLLM::slow("azure", Duration::from_secs(60))])
+        .with_timeout(Duration::from_secs(10));
+    let err = llm.generate("say hello").await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "all the LLMs failed: [azure: timed out after 10s]"
+    );
+
+    Ok(())
+}
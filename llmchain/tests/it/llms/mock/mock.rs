@@ -0,0 +1,110 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use futures::StreamExt;
+use llmchain::fake_embedding;
+use llmchain::ChatMessage;
+use llmchain::GenerateChunk;
+use llmchain::GenerateOptions;
+use llmchain::MockLLM;
+use llmchain::LLM;
+
+#[tokio::test]
+async fn test_mock_llm_responses() -> Result<()> {
+    let llm = MockLLM::create()
+        .with_responses(vec!["first", "second"])
+        .with_rule("weather", "sunny")
+        .with_rule("sql", "SELECT 1");
+
+    // Scripted, then rules, then echo.
+    assert_eq!(
+        llm.generate("what's the weather").await?.generation,
+        "first"
+    );
+    assert_eq!(
+        llm.generate("what's the weather").await?.generation,
+        "second"
+    );
+    assert_eq!(
+        llm.generate("what's the weather").await?.generation,
+        "sunny"
+    );
+    assert_eq!(llm.generate("write sql").await?.generation, "SELECT 1");
+    assert_eq!(llm.generate("hello").await?.generation, "hello");
+
+    let llm = llm.with_default_response("I don't know");
+    let result = llm
+        .chat(
+            vec![
+                ChatMessage::system("You are a helpful assistant."),
+                ChatMessage::user("hello"),
+            ],
+            &GenerateOptions::default(),
+        )
+        .await?;
+    assert_eq!(result.generation, "I don't know");
+    assert_eq!(result.completion_tokens, 4);
+    assert_eq!(result.provider, "MockLLM");
+
+    // Recorded.
+    let prompts = llm.prompts();
+    assert_eq!(prompts.len(), 6);
+    assert_eq!(prompts[3], "write sql");
+    assert_eq!(
+        prompts[5],
+        "System: You are a helpful assistant.\nUser: hello\nAssistant: "
+    );
+    assert_eq!(llm.requests()[5].len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mock_llm_stream() -> Result<()> {
+    let llm = MockLLM::create().with_default_response("Hello from llmchain");
+    let chunks = llm
+        .generate_stream("say hello")
+        .await?
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(chunks, vec![
+        GenerateChunk::Delta("Hello ".to_string()),
+        GenerateChunk::Delta("from ".to_string()),
+        GenerateChunk::Delta("llmchain".to_string()),
+        GenerateChunk::Usage {
+            prompt_tokens: 2,
+            completion_tokens: 5,
+            total_tokens: 7
+        },
+    ]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_mock_llm_embedding() -> Result<()> {
+    let llm = MockLLM::create().with_embedding_dimension(8);
+    let result = llm
+        .embedding(vec!["hello".to_string(), "world".to_string()])
+        .await?;
+    assert_eq!(result.embeddings.len(), 2);
+    assert_eq!(result.embeddings[0], fake_embedding("hello", 8));
+    assert_eq!(result.total_tokens, 2);
+    assert_eq!(llm.embedding_inputs(), vec!["hello", "world"]);
+
+    Ok(())
+}
@@ -12,6 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod anthropic;
 mod azure_openai;
+mod cached_llm;
+mod cassette;
+mod chat_message;
 mod databend;
+mod fallback_llm;
+mod mock;
+mod ollama;
 mod openai;
+mod openai_compatible;
+mod retrying_llm;
+mod tool;
+mod usage_tracker;
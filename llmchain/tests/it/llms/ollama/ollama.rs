@@ -0,0 +1,127 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use llmchain::ChatMessage;
+use llmchain::GenerateOptions;
+use llmchain::HttpError;
+use llmchain::Ollama;
+use llmchain::LLM;
+use serde_json::json;
+use wiremock::matchers::body_partial_json;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+
+#[tokio::test]
+async fn test_llm_ollama_chat() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .and(body_partial_json(json!({
+            "model": "llama3",
+            "messages": [
+                {"role": "system", "content": "You are a helpful assistant."},
+                {"role": "user", "content": "say Hello"}
+            ],
+            "stream": false,
+            "options": {"temperature": 0.0, "num_predict": 20, "stop": ["\n"]}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "model": "llama3",
+            "created_at": "2024-07-01T00:00:00Z",
+            "message": {"role": "assistant", "content": "Hello"},
+            "done": true,
+            "prompt_eval_count": 12,
+            "eval_count": 2
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let llm = Ollama::create(&server.uri(), "llama3").with_max_tokens(20);
+    let options = GenerateOptions::create()
+        .with_temperature(0.0)
+        .with_stop(vec!["\n"]);
+    let result = llm
+        .chat(
+            vec![
+                ChatMessage::system("You are a helpful assistant."),
+                ChatMessage::user("say Hello"),
+            ],
+            &options,
+        )
+        .await?;
+    assert_eq!(result.generation, "Hello");
+    assert_eq!(result.prompt_tokens, 12);
+    assert_eq!(result.completion_tokens, 2);
+    assert_eq!(result.total_tokens, 14);
+    assert_eq!(result.provider, "Ollama");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_ollama_embedding() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/embed"))
+        .and(body_partial_json(json!({
+            "model": "nomic-embed-text",
+            "input": ["hello", "world"]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "model": "nomic-embed-text",
+            "embeddings": [[0.1, 0.2], [0.3, 0.4]],
+            "prompt_eval_count": 2
+        })))
+        .mount(&server)
+        .await;
+
+    let llm = Ollama::create(&server.uri(), "llama3").with_embedding_model("nomic-embed-text");
+    let result = llm
+        .embedding(vec!["hello".to_string(), "world".to_string()])
+        .await?;
+    assert_eq!(result.embeddings, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+    assert_eq!(result.prompt_tokens, 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_ollama_error() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .respond_with(
+            ResponseTemplate::new(503)
+                .insert_header("retry-after", "3")
+                .set_body_json(json!({"error": "server busy"})),
+        )
+        .mount(&server)
+        .await;
+
+    let llm = Ollama::create(&server.uri(), "llama3");
+    let err = llm.generate("say Hello").await.unwrap_err();
+    let err = err.downcast_ref::<HttpError>().unwrap();
+    assert_eq!(err.status, 503);
+    assert_eq!(err.message, "server busy");
+    assert_eq!(err.retry_after, Some(Duration::from_secs(3)));
+
+    Ok(())
+}
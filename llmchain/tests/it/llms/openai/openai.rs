@@ -13,9 +13,71 @@
 // limitations under the License.
 
 use anyhow::Result;
+use futures::StreamExt;
+use llmchain::ChatMessage;
+use llmchain::GenerateChunk;
+use llmchain::GenerateOptions;
 use llmchain::OpenAIBuilder;
 use llmchain::OpenAIGenerateModel;
+use llmchain::Tool;
 use llmchain::LLM;
+use serde_json::json;
+use wiremock::matchers::body_partial_json;
+use wiremock::matchers::header;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+
+use crate::llms::cassette::Cassette;
+
+// Replays tests/testdata/cassettes/openai.json, a synthetic cassette recorded against a mock upstream
+// with hand-written answers, set LLMCHAIN_RECORD and OPENAI_API_KEY to record the real API.
+#[tokio::test]
+async fn test_llm_openai_cassette() -> Result<()> {
+    let cassette = Cassette::create(
+        "tests/testdata/cassettes/openai.json",
+        "https://api.openai.com",
+    )
+    .await?;
+    let api_key = std::env::var("OPENAI_API_KEY").unwrap_or("".to_string());
+    let llm = OpenAIBuilder::default()
+        .api_base(format!("{}/v1", cassette.url()))
+        .api_key(api_key)
+        .build()?;
+
+    let result = llm.generate("say Hello").await?;
+    assert!(result.generation.contains("Hello"));
+    assert_eq!(result.prompt_tokens, 10);
+    assert_eq!(result.completion_tokens, 9);
+    assert_eq!(result.total_tokens, 19);
+
+    let result = llm.embedding(vec!["llmchain".to_string()]).await?;
+    assert_eq!(result.embeddings.len(), 1);
+    assert_eq!(result.prompt_tokens, 3);
+
+    let mut stream = llm
+        .chat_stream(
+            vec![ChatMessage::user("say Hello")],
+            &GenerateOptions::default(),
+        )
+        .await?;
+    let mut generation = String::new();
+    let mut total_tokens = 0;
+    while let Some(chunk) = stream.next().await {
+        match chunk? {
+            GenerateChunk::Delta(delta) => generation.push_str(&delta),
+            GenerateChunk::Usage {
+                total_tokens: t, ..
+            } => total_tokens = t,
+        }
+    }
+    assert!(generation.contains("Hello"));
+    assert!(total_tokens > 0);
+
+    Ok(())
+}
 
 #[ignore]
 #[tokio::test]
@@ -52,6 +114,235 @@ async fn test_llm_openai_generate_gpt4() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_llm_openai_seed() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(header("authorization", "Bearer sk-test"))
+        .and(body_partial_json(json!({
+            "model": "gpt-3.5-turbo",
+            "messages": [{"role": "user", "content": "say Hello"}],
+            "temperature": 0.0,
+            "seed": 42
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-3.5-turbo",
+            "choices": [
+                {"index": 0, "message": {"role": "assistant", "content": "Hello"}, "finish_reason": "stop"}
+            ],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 1, "total_tokens": 11}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let llm = OpenAIBuilder::default()
+        .api_base(format!("{}/v1", server.uri()))
+        .api_key("sk-test".to_string())
+        .build()?;
+    let options = GenerateOptions::create()
+        .with_temperature(0.0)
+        .with_seed(42);
+    let result = llm
+        .chat(vec![ChatMessage::user("say Hello")], &options)
+        .await?;
+    assert_eq!(result.generation, "Hello");
+    assert_eq!(result.total_tokens, 11);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_openai_seed_stream() -> Result<()> {
+    let chunks = [
+        r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1700000000,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{"role":"assistant","content":"Hello"},"finish_reason":null}]}"#,
+        r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1700000000,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{"content":" world"},"finish_reason":null}]}"#,
+        r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1700000000,"model":"gpt-3.5-turbo","choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#,
+        "[DONE]",
+    ];
+    let body = chunks
+        .iter()
+        .map(|data| format!("data: {}\n\n", data))
+        .collect::<String>();
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_partial_json(json!({"stream": true, "seed": 42})))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let llm = OpenAIBuilder::default()
+        .api_base(format!("{}/v1", server.uri()))
+        .api_key("sk-test".to_string())
+        .build()?;
+    let options = GenerateOptions::create().with_seed(42);
+    let mut stream = llm
+        .chat_stream(vec![ChatMessage::user("say Hello")], &options)
+        .await?;
+    let mut deltas = vec![];
+    let mut usage = None;
+    while let Some(chunk) = stream.next().await {
+        match chunk? {
+            GenerateChunk::Delta(delta) => deltas.push(delta),
+            GenerateChunk::Usage { total_tokens, .. } => usage = Some(total_tokens),
+        }
+    }
+    assert_eq!(deltas, vec!["Hello", " world"]);
+    assert!(usage.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_openai_max_tokens_capped() -> Result<()> {
+    let server = MockServer::start().await;
+    // A 128k model answers at most 16384 tokens, not what the window leaves.
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_partial_json(json!({"model": "gpt-4o", "max_tokens": 16384})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-4o",
+            "choices": [
+                {"index": 0, "message": {"role": "assistant", "content": "Hello"}, "finish_reason": "stop"}
+            ],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 1, "total_tokens": 11}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let llm = OpenAIBuilder::default()
+        .api_base(format!("{}/v1", server.uri()))
+        .api_key("sk-test".to_string())
+        .generate_model(OpenAIGenerateModel::Gpt4o.to_string())
+        .build()?;
+    let result = llm.generate("say Hello").await?;
+    assert_eq!(result.generation, "Hello");
+
+    Ok(())
+}
+
+#[ignore]
+#[tokio::test]
+async fn test_llm_openai_generate_with_options() -> Result<()> {
+    let api_key = std::env::var("OPENAI_API_KEY").unwrap_or("".to_string());
+
+    let llm = OpenAIBuilder::default().api_key(api_key).build()?;
+    let options = GenerateOptions::create()
+        .with_model(&OpenAIGenerateModel::Gpt4.to_string())
+        .with_temperature(0.0)
+        .with_max_tokens(20)
+        .with_stop(vec!["5"])
+        .with_n(2);
+    let result = llm
+        .generate_with_options("count from 1 to 10, separated by comma", &options)
+        .await?;
+    assert_eq!(result.generations.len(), 2);
+    assert!(result.generation.contains('4'));
+    assert!(!result.generation.contains('5'));
+
+    Ok(())
+}
+
+#[ignore]
+#[tokio::test]
+async fn test_llm_openai_chat() -> Result<()> {
+    let api_key = std::env::var("OPENAI_API_KEY").unwrap_or("".to_string());
+
+    let llm = OpenAIBuilder::default().api_key(api_key).build()?;
+    let result = llm
+        .chat(
+            vec![
+                ChatMessage::system("You are a parrot, repeat the last word of the user."),
+                ChatMessage::user("Hello llmchain"),
+                ChatMessage::assistant("llmchain"),
+                ChatMessage::user("Hello world"),
+            ],
+            &GenerateOptions::default(),
+        )
+        .await?;
+    assert!(result.generation.contains("world"));
+
+    Ok(())
+}
+
+#[ignore]
+#[tokio::test]
+async fn test_llm_openai_chat_with_tools() -> Result<()> {
+    let api_key = std::env::var("OPENAI_API_KEY").unwrap_or("".to_string());
+
+    let llm = OpenAIBuilder::default().api_key(api_key).build()?;
+    let weather = Tool::create(
+        "get_weather",
+        "Get the current weather of a city",
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "city": { "type": "string" }
+            },
+            "required": ["city"]
+        }),
+    );
+    let messages = vec![ChatMessage::user("What's the weather like in Paris?")];
+    let result = llm
+        .chat_with_tools(
+            messages.clone(),
+            vec![weather.clone()],
+            &GenerateOptions::default(),
+        )
+        .await?;
+    assert_eq!(result.tool_calls.len(), 1);
+    assert_eq!(result.tool_calls[0].name, "get_weather");
+    assert_eq!(result.tool_calls[0].arguments["city"], "Paris");
+
+    // Feed the result of the call back.
+    let mut messages = messages;
+    messages.push(ChatMessage::assistant_tool_call(
+        result.tool_calls[0].clone(),
+    ));
+    messages.push(ChatMessage::tool("get_weather", r#"{"celsius": 22}"#));
+    let result = llm
+        .chat_with_tools(messages, vec![weather], &GenerateOptions::default())
+        .await?;
+    assert!(result.tool_calls.is_empty());
+    assert!(result.generation.contains("22"));
+
+    Ok(())
+}
+
+#[ignore]
+#[tokio::test]
+async fn test_llm_openai_generate_stream() -> Result<()> {
+    let api_key = std::env::var("OPENAI_API_KEY").unwrap_or("".to_string());
+
+    let llm = OpenAIBuilder::default().api_key(api_key).build()?;
+    let mut stream = llm.generate_stream("say Hello").await?;
+
+    let mut deltas = vec![];
+    let mut usage = None;
+    while let Some(chunk) = stream.next().await {
+        match chunk? {
+            GenerateChunk::Delta(delta) => deltas.push(delta),
+            GenerateChunk::Usage { total_tokens, .. } => usage = Some(total_tokens),
+        }
+    }
+    assert!(deltas.len() > 1);
+    assert!(deltas.concat().contains("Hello"));
+    assert!(usage.is_some());
+
+    Ok(())
+}
+
 #[ignore]
 #[tokio::test]
 async fn test_llm_openai_embedding() -> Result<()> {
@@ -0,0 +1,181 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use async_openai::error::OpenAIError;
+use llmchain::GenerateOptions;
+use llmchain::OpenAICompatible;
+use llmchain::LLM;
+use serde_json::json;
+use wiremock::matchers::body_partial_json;
+use wiremock::matchers::header;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+
+#[tokio::test]
+async fn test_llm_openai_compatible_generate() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(header("authorization", "Bearer sk-local"))
+        .and(body_partial_json(json!({
+            "model": "qwen2",
+            "messages": [{"role": "user", "content": "say Hello"}],
+            "n": 2
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "qwen2",
+            "choices": [
+                {"index": 0, "message": {"role": "assistant", "content": "Hello"}, "finish_reason": "stop"},
+                {"index": 1, "message": {"role": "assistant", "content": "Hello!"}, "finish_reason": "stop"}
+            ],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 3, "total_tokens": 13}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let llm = OpenAICompatible::create(&format!("{}/v1/", server.uri()), "llama3")
+        .with_api_key("sk-local");
+    let options = GenerateOptions::create().with_model("qwen2").with_n(2);
+    let result = llm.generate_with_options("say Hello", &options).await?;
+    assert_eq!(result.generation, "Hello");
+    assert_eq!(result.generations, vec!["Hello", "Hello!"]);
+    assert_eq!(result.total_tokens, 13);
+    assert_eq!(result.provider, "OpenAICompatible");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_openai_compatible_embedding() -> Result<()> {
+    let server = MockServer::start().await;
+    // Not in the order of the inputs.
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .and(body_partial_json(json!({"model": "bge-small"})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "object": "list",
+            "model": "bge-small",
+            "data": [
+                {"object": "embedding", "index": 1, "embedding": [0.3, 0.4]},
+                {"object": "embedding", "index": 0, "embedding": [0.1, 0.2]}
+            ],
+            "usage": {"prompt_tokens": 2, "total_tokens": 2}
+        })))
+        .mount(&server)
+        .await;
+
+    let llm = OpenAICompatible::create(&format!("{}/v1", server.uri()), "bge-small");
+    let result = llm
+        .embedding(vec!["hello".to_string(), "world".to_string()])
+        .await?;
+    assert_eq!(result.embeddings, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+    assert_eq!(result.total_tokens, 2);
+    assert_eq!(result.provider, "OpenAICompatible");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_openai_compatible_error() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": {"message": "model not found", "type": "invalid_request_error"}
+        })))
+        .mount(&server)
+        .await;
+
+    let llm = OpenAICompatible::create(&format!("{}/v1", server.uri()), "llama3");
+    let err = llm.generate("say Hello").await.unwrap_err();
+    let err = err.downcast_ref::<OpenAIError>().unwrap();
+    assert!(matches!(err, OpenAIError::ApiError(e) if e.message == "model not found"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_openai_compatible_context_length() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_partial_json(json!({"max_tokens": 100})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "llama3",
+            "choices": [
+                {"index": 0, "message": {"role": "assistant", "content": "Hello"}, "finish_reason": "stop"}
+            ],
+            "usage": {"prompt_tokens": 8000, "completion_tokens": 1, "total_tokens": 8001}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    // Unknown to the registry, the model is assumed to be 4K.
+    let prompt = "hello ".repeat(8000);
+    let llm = OpenAICompatible::create(&format!("{}/v1", server.uri()), "llama3");
+    let err = llm.generate(&prompt).await.unwrap_err();
+    assert!(err.to_string().contains("exceeds the context window"));
+
+    let llm = llm
+        .with_context_length(32768)
+        .with_reserved_completion_tokens(100)
+        .with_max_tokens(100);
+    let result = llm.generate(&prompt).await?;
+    assert_eq!(result.generation, "Hello");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_llm_openai_compatible_setter() -> Result<()> {
+    let server = MockServer::start().await;
+    for model in ["llama3", "qwen2"] {
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({"model": model})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 1700000000,
+                "model": model,
+                "choices": [
+                    {"index": 0, "message": {"role": "assistant", "content": model}, "finish_reason": "stop"}
+                ],
+                "usage": {"prompt_tokens": 10, "completion_tokens": 1, "total_tokens": 11}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    // The client built by the first call is dropped by the setter.
+    let llm = OpenAICompatible::create(&format!("{}/v1", server.uri()), "llama3");
+    assert_eq!(llm.generate("say Hello").await?.generation, "llama3");
+    let llm = llm.with_generate_model("qwen2");
+    assert_eq!(llm.generate("say Hello").await?.generation, "qwen2");
+
+    Ok(())
+}
@@ -0,0 +1,250 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use llmchain::ChatMessage;
+use llmchain::Document;
+use llmchain::Documents;
+use llmchain::Embedding;
+use llmchain::EmbeddingResult;
+use llmchain::GenerateOptions;
+use llmchain::GenerateResult;
+use llmchain::HttpError;
+use llmchain::LLMEmbedding;
+use llmchain::OpenAIBuilder;
+use llmchain::RetryingLLM;
+use llmchain::LLM;
+use parking_lot::Mutex;
+use reqwest::header::HeaderMap;
+use serde_json::json;
+use tokio::time::Instant;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+
+// Fails with the given errors first, then answers.
+struct FlakyLLM {
+    errors: Mutex<VecDeque<HttpError>>,
+    calls: AtomicUsize,
+}
+
+impl FlakyLLM {
+    fn create(errors: Vec<HttpError>) -> Arc<Self> {
+        Arc::new(FlakyLLM {
+            errors: Mutex::new(errors.into()),
+            calls: AtomicUsize::new(0),
+        })
+    }
+
+    fn call(&self) -> Result<()> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        match self.errors.lock().pop_front() {
+            Some(err) => Err(err.into()),
+            None => Ok(()),
+        }
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait::async_trait]
+impl LLM for FlakyLLM {
+    async fn embedding(&self, inputs: Vec<String>) -> Result<EmbeddingResult> {
+        self.call()?;
+        Ok(EmbeddingResult {
+            prompt_tokens: 0,
+            total_tokens: 0,
+            embeddings: inputs.iter().map(|_| vec![1.0]).collect(),
+            ..Default::default()
+        })
+    }
+
+    async fn chat(
+        &self,
+        _messages: Vec<ChatMessage>,
+        _options: &GenerateOptions,
+    ) -> Result<GenerateResult> {
+        self.call()?;
+        Ok(GenerateResult {
+            generation: "hello".to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_retrying_llm_transient_errors() -> Result<()> {
+    let flaky = FlakyLLM::create(vec![
+        HttpError::create(503, "unavailable"),
+        HttpError::create(429, "rate limited"),
+    ]);
+    let llm = RetryingLLM::create(flaky.clone()).with_initial_backoff(Duration::from_millis(1));
+
+    let result = llm.generate("say hello").await?;
+    assert_eq!(result.generation, "hello");
+    assert_eq!(flaky.calls(), 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retrying_llm_permanent_errors() -> Result<()> {
+    // Not retried.
+    let flaky = FlakyLLM::create(vec![HttpError::create(400, "bad request")]);
+    let llm = RetryingLLM::create(flaky.clone()).with_initial_backoff(Duration::from_millis(1));
+    let err = llm.generate("say hello").await.unwrap_err();
+    assert_eq!(err.to_string(), "http status 400: bad request");
+    assert_eq!(flaky.calls(), 1);
+
+    // Out of retries.
+    let flaky = FlakyLLM::create(vec![HttpError::create(500, "internal error"); 3]);
+    let llm = RetryingLLM::create(flaky.clone())
+        .with_initial_backoff(Duration::from_millis(1))
+        .with_max_retries(2);
+    assert!(llm.generate("say hello").await.is_err());
+    assert_eq!(flaky.calls(), 3);
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retrying_llm_retry_after() -> Result<()> {
+    let mut headers = HeaderMap::new();
+    headers.insert("retry-after", "20".parse()?);
+    let error = HttpError::create(429, "rate limited").with_headers(&headers);
+    assert_eq!(error.retry_after, Some(Duration::from_secs(20)));
+
+    let flaky = FlakyLLM::create(vec![error]);
+    let llm = RetryingLLM::create(flaky.clone()).with_initial_backoff(Duration::from_millis(1));
+
+    let now = Instant::now();
+    llm.generate("say hello").await?;
+    assert!(now.elapsed() >= Duration::from_secs(20));
+    assert_eq!(flaky.calls(), 2);
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retrying_llm_rate_limits() -> Result<()> {
+    let flaky = FlakyLLM::create(vec![]);
+
+    // The third request waits for the first one to leave the window.
+    let llm = RetryingLLM::create(flaky.clone()).with_requests_per_minute(2);
+    let now = Instant::now();
+    for _ in 0..3 {
+        llm.generate("say hello").await?;
+    }
+    assert!(now.elapsed() >= Duration::from_secs(60));
+    assert!(now.elapsed() < Duration::from_secs(120));
+
+    // "hello world" is 2 tokens, only 2 embeddings fit in a minute.
+    let llm = RetryingLLM::create(flaky.clone()).with_tokens_per_minute(4);
+    let now = Instant::now();
+    for _ in 0..3 {
+        llm.embedding(vec!["hello world".to_string()]).await?;
+    }
+    assert!(now.elapsed() >= Duration::from_secs(60));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_retrying_llm_openai_mock_server() -> Result<()> {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .respond_with(ResponseTemplate::new(429).set_body_json(json!({
+            "error": {
+                "message": "Rate limit reached for requests",
+                "type": "requests",
+                "param": null,
+                "code": "rate_limit_exceeded"
+            }
+        })))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1700000000,
+            "model": "gpt-3.5-turbo",
+            "usage": {"prompt_tokens": 10, "completion_tokens": 1, "total_tokens": 11},
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hello"},
+                "finish_reason": "stop"
+            }]
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/embeddings"))
+        .respond_with(ResponseTemplate::new(500).set_body_json(json!({
+            "error": {
+                "message": "The server had an error while processing your request",
+                "type": "server_error",
+                "param": null,
+                "code": null
+            }
+        })))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/embeddings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "object": "list",
+            "model": "text-embedding-ada-002",
+            "data": [{"index": 0, "object": "embedding", "embedding": [0.1, 0.2]}],
+            "usage": {"prompt_tokens": 2, "total_tokens": 2}
+        })))
+        .mount(&server)
+        .await;
+
+    let openai = OpenAIBuilder::default()
+        .api_base(server.uri())
+        .api_key("sk-test".to_string())
+        .rate_limit_backoff(false)
+        .build()?;
+    let llm = RetryingLLM::create(Arc::new(openai)).with_initial_backoff(Duration::from_millis(1));
+
+    let result = llm.generate("say Hello").await?;
+    assert_eq!(result.generation, "Hello");
+    assert_eq!(result.total_tokens, 11);
+
+    let embedding = LLMEmbedding::create(llm);
+    let documents = Documents::from(vec![Document::create("", "hello world")]);
+    let embeddings = embedding.embed_documents(&documents).await?;
+    assert_eq!(embeddings, vec![vec![0.1, 0.2]]);
+
+    assert_eq!(server.received_requests().await.unwrap().len(), 4);
+
+    Ok(())
+}
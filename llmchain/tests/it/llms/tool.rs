@@ -0,0 +1,26 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use llmchain::ToolCall;
+
+#[test]
+fn test_tool_call_arguments() {
+    let tool_call = ToolCall::create("get_weather", r#"{"city": "Paris", "days": 3}"#).unwrap();
+    assert_eq!(tool_call.name, "get_weather");
+    assert_eq!(tool_call.arguments["city"], "Paris");
+    assert_eq!(tool_call.arguments["days"], 3);
+
+    let result = ToolCall::create("get_weather", r#"{"city": "Paris""#);
+    assert!(result.is_err());
+}
@@ -0,0 +1,110 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use futures::StreamExt;
+use llmchain::EmbeddingResult;
+use llmchain::GenerateChunk;
+use llmchain::GenerateResult;
+use llmchain::GenerateStream;
+use llmchain::UsageKind;
+use llmchain::UsageTracker;
+
+#[test]
+fn test_usage_tracker() {
+    let tracker = UsageTracker::create();
+
+    let generate_result = GenerateResult {
+        prompt_tokens: 1000,
+        completion_tokens: 500,
+        total_tokens: 1500,
+        ..Default::default()
+    };
+    tracker.record_generation("gpt-4", &generate_result);
+    tracker.record_generation("gpt-4-0613", &generate_result);
+    tracker.record_generation("gpt-4", &generate_result);
+
+    let embedding_result = EmbeddingResult {
+        prompt_tokens: 2000,
+        total_tokens: 2000,
+        ..Default::default()
+    };
+    tracker.record_embedding("text-embedding-ada-002", &embedding_result);
+    tracker.record_embedding("my-local-model", &embedding_result);
+
+    let usage = tracker.usage("gpt-4", UsageKind::Generation);
+    assert_eq!(usage.requests, 2);
+    assert_eq!(usage.prompt_tokens, 2000);
+    assert_eq!(usage.completion_tokens, 1000);
+    assert_eq!(usage.total_tokens, 3000);
+    assert!((usage.cost - 0.12).abs() < 1e-9);
+
+    // Unknown models are counted without a price.
+    let usage = tracker.usage("my-local-model", UsageKind::Embedding);
+    assert_eq!(usage.total_tokens, 2000);
+    assert_eq!(usage.cost, 0.0);
+
+    let usages = tracker.usages();
+    let keys = usages
+        .iter()
+        .map(|(model, kind, _)| format!("{}/{}", model, kind))
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec![
+        "gpt-4/generation",
+        "gpt-4-0613/generation",
+        "my-local-model/embedding",
+        "text-embedding-ada-002/embedding",
+    ]);
+
+    let generation = tracker.total_by_kind(UsageKind::Generation);
+    assert_eq!(generation.requests, 3);
+    assert!((generation.cost - 0.18).abs() < 1e-9);
+
+    let embedding = tracker.total_by_kind(UsageKind::Embedding);
+    assert_eq!(embedding.requests, 2);
+    assert_eq!(embedding.prompt_tokens, 4000);
+
+    let total = tracker.total();
+    assert_eq!(total.requests, 5);
+    assert_eq!(total.total_tokens, 8500);
+    assert!((total.cost - generation.cost - embedding.cost).abs() < 1e-9);
+
+    tracker.reset();
+    assert_eq!(tracker.total().requests, 0);
+}
+
+#[tokio::test]
+async fn test_usage_tracker_stream() -> Result<()> {
+    let tracker = UsageTracker::create();
+    let stream: GenerateStream = Box::pin(futures::stream::iter(vec![
+        Ok(GenerateChunk::Delta("hello".to_string())),
+        Ok(GenerateChunk::Usage {
+            prompt_tokens: 10,
+            completion_tokens: 1,
+            total_tokens: 11,
+        }),
+    ]));
+
+    let chunks = tracker
+        .track_stream("gpt-3.5-turbo", stream)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(chunks.len(), 2);
+
+    let usage = tracker.usage("gpt-3.5-turbo", UsageKind::Generation);
+    assert_eq!(usage.requests, 1);
+    assert_eq!(usage.prompt_tokens, 10);
+    assert_eq!(usage.completion_tokens, 1);
+    Ok(())
+}
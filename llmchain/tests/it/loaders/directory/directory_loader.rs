@@ -37,6 +37,13 @@ async fn test_directory_loader() -> Result<()> {
         .load(DocumentPath::from_string(&directory_dir))
         .await?;
     assert_eq!(documents.len(), 2);
+    for document in documents.iter() {
+        assert_eq!(
+            document.metadata["directory"].as_str(),
+            Some(directory_dir.as_str())
+        );
+        assert_eq!(document.metadata["language"].as_str(), Some("markdown"));
+    }
 
     // Check.
     let mut mint = Mint::new(&testdata_dir);
@@ -16,9 +16,11 @@ use std::io::Write;
 
 use anyhow::Result;
 use goldenfile::Mint;
+use llmchain::Document;
 use llmchain::DocumentLoader;
 use llmchain::DocumentPath;
 use llmchain::DocumentSplitter;
+use llmchain::Documents;
 use llmchain::GithubPRDiffSplitter;
 use llmchain::GithubPRLoader;
 
@@ -64,3 +66,36 @@ async fn test_github_pr_splitter_default() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_github_pr_splitter_metadata() -> Result<()> {
+    let diff = "\
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1 +1 @@
+-a
++b
+--- a/src/b.rs
++++ b/src/b.rs
+@@ -1 +1 @@
+-c
++d
+";
+    let documents = Documents::from(vec![Document::create(
+        "https://github.com/datafuselabs/databend/pull/1",
+        diff,
+    )
+    .with_metadata("pr_number", 1i64)]);
+
+    let github_pr_splitter = GithubPRDiffSplitter::create();
+    let documents = github_pr_splitter.split_documents(&documents)?;
+    assert_eq!(documents.len(), 1);
+    let document = documents.iter().next().unwrap();
+    assert_eq!(document.metadata["pr_number"].as_i64(), Some(1));
+    assert_eq!(
+        document.metadata["files"].as_str(),
+        Some("src/a.rs,src/b.rs")
+    );
+
+    Ok(())
+}
@@ -16,9 +16,11 @@ use std::io::Write;
 
 use anyhow::Result;
 use goldenfile::Mint;
+use llmchain::Document;
 use llmchain::DocumentLoader;
 use llmchain::DocumentPath;
 use llmchain::DocumentSplitter;
+use llmchain::Documents;
 use llmchain::LocalDisk;
 use llmchain::MarkdownLoader;
 use llmchain::MarkdownSplitter;
@@ -148,3 +150,30 @@ async fn test_markdown_splitter_custom_separator() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_markdown_splitter_metadata() -> Result<()> {
+    let content = "# Guide\nintro\n## Install\nstep one\n```\n# not a heading\n```\n### From source\ncargo build\n## Usage\nrun it";
+    let documents = Documents::from(vec![Document::create("guide.md", content)]);
+
+    let markdown_splitter = MarkdownSplitter::create().with_chunk_size(1);
+    let documents = markdown_splitter.split_documents(&documents)?;
+    let chunks = documents
+        .iter()
+        .map(|x| {
+            (
+                x.metadata.get("heading").map(|x| x.to_string()),
+                x.metadata["line_start"].as_i64().unwrap(),
+                x.metadata["line_end"].as_i64().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(chunks, vec![
+        (Some("Guide".to_string()), 1, 2),
+        (Some("Guide > Install".to_string()), 3, 7),
+        (Some("Guide > Install > From source".to_string()), 8, 9),
+        (Some("Guide > Usage".to_string()), 10, 11),
+    ]);
+
+    Ok(())
+}
@@ -55,3 +55,23 @@ async fn test_text_loader() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_text_loader_metadata() -> Result<()> {
+    let curdir = std::env::current_dir()?.to_str().unwrap().to_string();
+    let text_file = format!("{}/tests/testdata/loaders/text/example.txt", curdir);
+
+    let text_loader = TextLoader::create(LocalDisk::create()?);
+    let documents = text_loader
+        .load(DocumentPath::from_string(&text_file))
+        .await?;
+    let document = documents.iter().next().unwrap();
+    assert_eq!(
+        document.metadata["source"].as_str(),
+        Some(text_file.as_str())
+    );
+    assert_eq!(document.metadata["language"].as_str(), Some("text"));
+    assert!(document.metadata.contains_key("last_modified"));
+
+    Ok(())
+}
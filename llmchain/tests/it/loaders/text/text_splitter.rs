@@ -16,9 +16,11 @@ use std::io::Write;
 
 use anyhow::Result;
 use goldenfile::Mint;
+use llmchain::Document;
 use llmchain::DocumentLoader;
 use llmchain::DocumentPath;
 use llmchain::DocumentSplitter;
+use llmchain::Documents;
 use llmchain::LocalDisk;
 use llmchain::TextLoader;
 use llmchain::TextSplitter;
@@ -103,3 +105,32 @@ async fn test_text_splitter_10() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_text_splitter_metadata() -> Result<()> {
+    let documents = Documents::from(vec![
+        Document::create("a.txt", "one\ntwo\nthree\nfour").with_metadata("author", "bohu")
+    ]);
+
+    let text_splitter = TextSplitter::create().with_chunk_size(8);
+    let documents = text_splitter.split_documents(&documents)?;
+    let chunks = documents
+        .iter()
+        .map(|x| {
+            assert_eq!(x.path, "a.txt");
+            assert_eq!(x.metadata["author"].as_str(), Some("bohu"));
+            (
+                x.content.clone(),
+                x.metadata["chunk"].as_i64().unwrap(),
+                x.metadata["line_start"].as_i64().unwrap(),
+                x.metadata["line_end"].as_i64().unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(chunks, vec![
+        ("one two three".to_string(), 0, 1, 3),
+        ("four".to_string(), 1, 4, 4),
+    ]);
+
+    Ok(())
+}
@@ -12,9 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod caches;
 mod common;
 mod embeddings;
+mod indexers;
 mod llms;
 mod loaders;
+mod memory;
 mod prompts;
 mod vector_stores;
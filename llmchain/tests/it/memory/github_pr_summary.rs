@@ -0,0 +1,46 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use llmchain::Document;
+use llmchain::Documents;
+use llmchain::GithubPRSummary;
+use llmchain::MockLLM;
+use llmchain::Summarize;
+
+#[tokio::test]
+async fn test_github_pr_summary() -> Result<()> {
+    // The first matching rule answers, the final summary prompt also contains the file names.
+    let llm = MockLLM::create()
+        .with_rule("summarizing code changes", "* **Fix a and add b**")
+        .with_rule("a.rs", "[CHANGE] a.rs\n- fix a")
+        .with_rule("b.rs", "[ADD] b.rs\n- add b");
+
+    let summary = GithubPRSummary::create(llm.clone());
+    let documents = Documents::from(vec![
+        Document::create("a.rs", "--- a/a.rs\n+++ b/a.rs\n-let a = 1;\n+let a = 2;"),
+        Document::create("b.rs", "--- /dev/null\n+++ b/b.rs\n+let b = 1;"),
+    ]);
+    summary.add_documents(&documents).await?;
+    assert_eq!(summary.final_summary().await?, "* **Fix a and add b**");
+
+    // One request per document, and the final one over their summaries.
+    let prompts = llm.prompts();
+    assert_eq!(prompts.len(), 3);
+    assert!(prompts[0].contains("+let a = 2;"));
+    assert!(prompts[2].contains("[CHANGE] a.rs\n- fix a\n[ADD] b.rs\n- add b"));
+    assert!(summary.tokens() > 0);
+
+    Ok(())
+}
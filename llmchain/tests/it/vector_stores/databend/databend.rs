@@ -19,6 +19,7 @@ use llmchain::DatabendEmbedding;
 use llmchain::DatabendVectorStore;
 use llmchain::Document;
 use llmchain::Documents;
+use llmchain::Filter;
 use llmchain::VectorStore;
 
 #[tokio::test]
@@ -32,22 +33,92 @@ async fn test_vector_stores_databend() -> Result<()> {
     let documents = Documents::from(vec![
         Document::create("1.md", "hello"),
         Document::create("2.md", "llmchain.rs"),
+        Document::create("3.md", "llmchain.rs").with_metadata("lang", "it's"),
     ]);
     let result = databend.add_documents(&documents).await?;
-    assert_eq!(result.len(), 2);
+    assert_eq!(result.len(), 3);
 
-    let similarities = databend.similarity_search("llmchain", 1).await?;
+    let filter = Filter::path_prefix("2.md");
+    let similarities = databend
+        .similarity_search_with_filter("llmchain", 1, &filter)
+        .await?
+        .into_iter()
+        .map(|x| x.0)
+        .collect::<Vec<_>>();
     assert_eq!(similarities.len(), 1);
 
     let expect_document = Document {
         path: "2.md".to_string(),
         content: "llmchain.rs".to_string(),
         content_md5: "033d6bd60a5237d54fa8331dd2ca1325".to_string(),
+        metadata: Default::default(),
     };
 
     let actual_document = similarities[0].clone();
 
     assert_eq!(expect_document, actual_document);
 
+    // Above the min similarity of the store.
+    let similarities = databend
+        .similarity_search_with_filter("llmchain", 1, &filter)
+        .await?;
+    assert_eq!(similarities[0].0, expect_document);
+    assert!(similarities[0].1 > 0.5);
+
+    let filter = Filter::eq("lang", "it's");
+    let similarities = databend
+        .similarity_search_with_filter("llmchain", 2, &filter)
+        .await?;
+    assert_eq!(similarities.len(), 1);
+    assert_eq!(similarities[0].0.path, "3.md");
+
+    // Unchanged, not added again.
+    let uuids = databend.upsert_documents(&documents).await?;
+    assert_eq!(uuids, result);
+    assert_eq!(databend.delete(&uuids[0..1]).await?, 1);
+    assert_eq!(databend.delete_by_path("3.md").await?, 1);
+    let keys = databend
+        .document_keys(&["1.md".to_string(), "2.md".to_string()])
+        .await?;
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].uuid, uuids[1]);
+
+    // The metadata is updated in place.
+    let updated = databend
+        .update_metadata(&[
+            (uuids[1].clone(), keys[0].metadata.clone()),
+            ("unknown".to_string(), Default::default()),
+        ])
+        .await?;
+    assert_eq!(updated, 1);
+
+    // The content is stored as it is.
+    let content = "# llmchain\n\n\tit's a 'rust' crate\r\nC:\\dir\\n 数据库";
+    let document = Document::create("4.md", content).with_metadata("note", "a\nb");
+    databend
+        .add_documents(&Documents::from(vec![document.clone()]))
+        .await?;
+    let similarities = databend
+        .similarity_search_with_filter("llmchain", 1, &Filter::eq("note", "a\nb"))
+        .await?;
+    assert_eq!(similarities[0].0, document);
+    assert_eq!(databend.delete_by_path("4.md").await?, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_databend_invalid_identifiers() -> Result<()> {
+    let dsn = "databend://root:@localhost:8000/default?sslmode=disable";
+    let embedding = Arc::new(DatabendEmbedding::create(dsn));
+
+    let databend =
+        DatabendVectorStore::create(dsn, embedding.clone()).with_table("docs; DROP TABLE users");
+    let error = databend.init().await.unwrap_err();
+    assert!(error.to_string().contains("invalid SQL identifier"));
+
+    let databend = DatabendVectorStore::create(dsn, embedding).with_database("a.b");
+    assert!(databend.delete_by_path("1.md").await.is_err());
+
     Ok(())
 }
@@ -0,0 +1,196 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use llmchain::Document;
+use llmchain::Documents;
+use llmchain::Filter;
+use llmchain::HashingEmbedding;
+use llmchain::SqliteVectorStore;
+use llmchain::VectorStore;
+
+fn document() -> Document {
+    Document::create("docs/guide/install.md", "cargo add llmchain")
+        .with_metadata("repository", "datafuselabs/llmchain")
+        .with_metadata("language", "markdown")
+        .with_metadata("version", 3i64)
+        .with_metadata("generated", false)
+}
+
+#[test]
+fn test_vector_stores_filter_matches() {
+    let document = document();
+
+    assert!(Filter::default().matches(&document));
+    assert!(Filter::eq("language", "markdown").matches(&document));
+    assert!(!Filter::eq("language", "rust").matches(&document));
+    assert!(!Filter::eq("missing", "markdown").matches(&document));
+    // The types must match too.
+    assert!(!Filter::eq("version", "3").matches(&document));
+    assert!(Filter::eq("generated", false).matches(&document));
+    assert!(Filter::eq("path", "docs/guide/install.md").matches(&document));
+
+    assert!(Filter::any_of("language", vec!["rust", "markdown"]).matches(&document));
+    assert!(!Filter::any_of::<&str>("language", vec![]).matches(&document));
+
+    assert!(Filter::range("version", Some(3i64), Some(3i64)).matches(&document));
+    assert!(Filter::range("version", None, Some(10i64)).matches(&document));
+    assert!(!Filter::range("version", Some(4i64), None).matches(&document));
+    assert!(!Filter::range("version", Some("1"), None).matches(&document));
+    assert!(Filter::range("language", Some("m"), Some("n")).matches(&document));
+
+    assert!(Filter::path_prefix("docs/").matches(&document));
+    assert!(!Filter::path_prefix("src/").matches(&document));
+
+    let filter = Filter::and(vec![
+        Filter::eq("repository", "datafuselabs/llmchain"),
+        Filter::path_prefix("docs/"),
+    ]);
+    assert!(filter.matches(&document));
+    let filter = Filter::and(vec![filter, Filter::eq("language", "rust")]);
+    assert!(!filter.matches(&document));
+    let filter = Filter::or(vec![filter, Filter::eq("language", "markdown")]);
+    assert!(filter.matches(&document));
+    assert!(!Filter::or(vec![]).matches(&document));
+}
+
+#[test]
+fn test_vector_stores_filter_to_sql() {
+    let filter = Filter::and(vec![
+        Filter::eq("language", "rust"),
+        Filter::any_of("version", vec![1i64, 2]),
+        Filter::range("updated_at", Some("2023-01-01"), None),
+        Filter::eq("generated", false),
+        Filter::path_prefix("docs/"),
+    ]);
+    assert_eq!(
+        filter.to_sql(),
+        "(as_string(get(parse_json(metadata), 'language')) = 'rust' \
+         AND (as_integer(get(parse_json(metadata), 'version')) = 1 \
+         OR as_integer(get(parse_json(metadata), 'version')) = 2) \
+         AND (as_string(get(parse_json(metadata), 'updated_at')) >= '2023-01-01') \
+         AND as_boolean(get(parse_json(metadata), 'generated')) = false \
+         AND LEFT(path, 5) = 'docs/')"
+    );
+
+    assert_eq!(Filter::default().to_sql(), "1 = 1");
+    assert_eq!(Filter::any_of::<&str>("language", vec![]).to_sql(), "1 = 0");
+    assert_eq!(Filter::eq("path", "a.md").to_sql(), "path = 'a.md'");
+    assert_eq!(Filter::eq("path", 1i64).to_sql(), "1 = 0");
+    assert_eq!(
+        Filter::range::<i64>("version", None, None).to_sql(),
+        "get(parse_json(metadata), 'version') IS NOT NULL"
+    );
+
+    // The keys are no paths.
+    assert_eq!(
+        Filter::eq("docs.version", 2i64).to_sql(),
+        "as_integer(get(parse_json(metadata), 'docs.version')) = 2"
+    );
+
+    // The keys and the values are quoted.
+    assert_eq!(
+        Filter::eq("it's", "' OR 1 = 1 --").to_sql(),
+        "as_string(get(parse_json(metadata), 'it''s')) = ''' OR 1 = 1 --'"
+    );
+    assert_eq!(Filter::path_prefix("ä'").to_sql(), "LEFT(path, 2) = 'ä'''");
+}
+
+// The same filters through `matches` and the SQL of the SQLite store, both must select the same documents.
+#[tokio::test]
+async fn test_vector_stores_filter_rust_and_sql() -> Result<()> {
+    let documents = vec![
+        document(),
+        Document::create("docs/a.md", "hello")
+            .with_metadata("language", "rust")
+            .with_metadata("version", "3")
+            .with_metadata("generated", "false")
+            .with_metadata("docs.version", 2i64),
+        Document::create("src/b.rs", "world")
+            .with_metadata("docs", "nested")
+            .with_metadata("version", 10i64)
+            .with_metadata("generated", true),
+    ];
+    let store = SqliteVectorStore::create(":memory:", HashingEmbedding::create(64))?
+        .with_min_similarity(-2.0);
+    store.init().await?;
+    store
+        .add_documents(&Documents::from(documents.clone()))
+        .await?;
+
+    let cases = vec![
+        (Filter::default(), vec![0, 1, 2]),
+        (Filter::eq("language", "markdown"), vec![0]),
+        (Filter::eq("version", 3i64), vec![0]),
+        (Filter::eq("version", "3"), vec![1]),
+        (Filter::eq("generated", false), vec![0]),
+        (Filter::eq("generated", "false"), vec![1]),
+        (Filter::eq("generated", true), vec![2]),
+        (Filter::eq("docs.version", 2i64), vec![1]),
+        (Filter::eq("docs", "nested"), vec![2]),
+        (Filter::eq("missing", "x"), vec![]),
+        (Filter::eq("path", "src/b.rs"), vec![2]),
+        (Filter::eq("path", 1i64), vec![]),
+        (Filter::any_of("version", vec![3i64, 10]), vec![0, 2]),
+        (Filter::any_of::<i64>("version", vec![]), vec![]),
+        (Filter::range("version", Some(4i64), None), vec![2]),
+        (Filter::range("version", Some(3i64), Some(3i64)), vec![0]),
+        (Filter::range("version", Some("1"), None), vec![1]),
+        (Filter::range("generated", Some(false), Some(true)), vec![
+            0, 2,
+        ]),
+        (Filter::range::<i64>("docs.version", None, None), vec![1]),
+        (Filter::range("path", Some("docs/b"), None), vec![0, 2]),
+        (Filter::path_prefix("docs/"), vec![0, 1]),
+        (Filter::path_prefix(""), vec![0, 1, 2]),
+        (
+            Filter::or(vec![
+                Filter::eq("language", "rust"),
+                Filter::and(vec![
+                    Filter::path_prefix("src/"),
+                    Filter::eq("generated", true),
+                ]),
+            ]),
+            vec![1, 2],
+        ),
+        (Filter::or(vec![]), vec![]),
+    ];
+
+    for (filter, expected) in cases {
+        let expected = expected
+            .into_iter()
+            .map(|i: usize| documents[i].path.clone())
+            .collect::<Vec<_>>();
+
+        let matched = documents
+            .iter()
+            .filter(|x| filter.matches(x))
+            .map(|x| x.path.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(matched, expected, "matches {:?}", filter);
+
+        let mut selected = store
+            .similarity_search_with_filter("hello", 10, &filter)
+            .await?
+            .into_iter()
+            .map(|x| x.0.path)
+            .collect::<Vec<_>>();
+        selected.sort();
+        let mut expected = expected;
+        expected.sort();
+        assert_eq!(selected, expected, "sqlite {:?}", filter);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use llmchain::fake_embedding;
+use llmchain::HnswConfig;
+use llmchain::HnswIndex;
+use llmchain::SimilarityMetric;
+
+fn recall(index: &HnswIndex, queries: &[Vec<f32>], k: usize) -> f32 {
+    let mut found = 0;
+    for query in queries {
+        let expected = index
+            .brute_force_search(query, k)
+            .into_iter()
+            .map(|x| x.0)
+            .collect::<Vec<_>>();
+        let actual = index.search(query, k);
+        assert_eq!(actual.len(), k);
+        found += actual.iter().filter(|x| expected.contains(&x.0)).count();
+    }
+    found as f32 / (queries.len() * k) as f32
+}
+
+#[test]
+fn test_hnsw_index_recall() {
+    let mut index = HnswIndex::create(HnswConfig {
+        m: 8,
+        ef_construction: 64,
+        ef_search: 64,
+        ..Default::default()
+    });
+    for i in 0..1000 {
+        assert_eq!(index.insert(fake_embedding(&i.to_string(), 16)), i);
+    }
+    assert_eq!(index.len(), 1000);
+
+    let queries = (0..50)
+        .map(|i| fake_embedding(&format!("query {}", i), 16))
+        .collect::<Vec<_>>();
+    assert!(recall(&index, &queries, 10) > 0.9);
+
+    // A vector in the index is its own nearest neighbour.
+    let result = index.search(&fake_embedding("42", 16), 1);
+    assert_eq!(result[0].0, 42);
+    assert!((result[0].1 - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn test_hnsw_index_delete() {
+    let mut index = HnswIndex::create(HnswConfig {
+        metric: SimilarityMetric::L2,
+        ..Default::default()
+    });
+    assert!(index.search(&[0.0, 0.0], 3).is_empty());
+
+    for i in 0..100 {
+        index.insert(vec![i as f32, 0.0]);
+    }
+    let ids = index
+        .search(&[10.2, 0.0], 3)
+        .into_iter()
+        .map(|x| x.0)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![10, 11, 9]);
+
+    assert!(index.delete(10));
+    assert!(!index.delete(10));
+    assert!(!index.contains(10));
+    assert_eq!(index.len(), 99);
+    let ids = index
+        .search(&[10.2, 0.0], 3)
+        .into_iter()
+        .map(|x| x.0)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![11, 9, 12]);
+
+    // Still searchable after deleting the most of it.
+    for i in 0..95 {
+        index.delete(i);
+    }
+    let ids = index
+        .search(&[0.0, 0.0], 10)
+        .into_iter()
+        .map(|x| x.0)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![95, 96, 97, 98, 99]);
+}
+
+#[test]
+fn test_hnsw_index_serde() {
+    let mut index = HnswIndex::create(HnswConfig::default());
+    for i in 0..200 {
+        index.insert(fake_embedding(&i.to_string(), 8));
+    }
+    index.delete(7);
+
+    let loaded: HnswIndex = serde_json::from_str(&serde_json::to_string(&index).unwrap()).unwrap();
+    let query = fake_embedding("query", 8);
+    assert_eq!(loaded.len(), 199);
+    assert_eq!(loaded.search(&query, 5), index.search(&query, 5));
+}
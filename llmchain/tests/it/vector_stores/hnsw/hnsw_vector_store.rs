@@ -0,0 +1,184 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use llmchain::Document;
+use llmchain::Documents;
+use llmchain::Filter;
+use llmchain::HashingEmbedding;
+use llmchain::HnswVectorStore;
+use llmchain::VectorStore;
+
+fn documents() -> Documents {
+    Documents::from(vec![
+        Document::create("1.md", "hello world"),
+        Document::create("2.md", "llmchain rust library for llm"),
+        Document::create("3.md", "llmchain rust examples"),
+        Document::create("4.md", ""),
+    ])
+}
+
+#[tokio::test]
+async fn test_vector_stores_hnsw() -> Result<()> {
+    let store = HnswVectorStore::create(HashingEmbedding::create(256))
+        .with_ef_search(32)
+        .with_min_similarity(0.0);
+    store.init().await?;
+
+    let uuids = store.add_documents(&documents()).await?;
+    assert_eq!(uuids.len(), 4);
+    assert_eq!(store.len(), 4);
+
+    let similarities = store
+        .similarity_search_with_score("llmchain rust examples", 2)
+        .await?;
+    assert_eq!(similarities.len(), 2);
+    assert_eq!(similarities[0].0.path, "3.md");
+    assert_eq!(similarities[1].0.path, "2.md");
+    assert!((similarities[0].1 - 1.0).abs() < 1e-5);
+    assert!(similarities[1].1 < similarities[0].1);
+
+    assert_eq!(store.delete(&uuids[2..3]).await?, 1);
+    assert_eq!(store.delete(&uuids[2..3]).await?, 0);
+    assert_eq!(store.len(), 3);
+    let similarities = store.similarity_search("llmchain rust examples", 2).await?;
+    assert_eq!(similarities[0].path, "2.md");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_hnsw_save_load() -> Result<()> {
+    let file = std::env::temp_dir().join(format!("llmchain-hnsw-{}.json", uuid::Uuid::new_v4()));
+    let file = file.to_str().unwrap();
+
+    let embedding = HashingEmbedding::create(256);
+    let store = HnswVectorStore::create(embedding.clone()).with_min_similarity(0.0);
+    let uuids = store.add_documents(&documents()).await?;
+    store.delete(&uuids[0..1]).await?;
+    store.save(file)?;
+
+    // The deleted document is dropped from the saved graph, the others keep their uuids.
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(file)?)?;
+    let records = saved["records"].as_array().unwrap();
+    assert_eq!(records.len(), 3);
+    assert!(records.iter().all(|x| !x.is_null()));
+    assert_eq!(saved["index"]["deleted"], 1);
+    let keys = store.document_keys(&["2.md".to_string()]).await?;
+    assert_eq!(keys[0].uuid, uuids[1]);
+
+    let loaded = HnswVectorStore::create(embedding).with_min_similarity(0.0);
+    loaded.load(file)?;
+    assert_eq!(loaded.len(), 3);
+    assert_eq!(
+        loaded.similarity_search("hello", 3).await?,
+        store.similarity_search("hello", 3).await?
+    );
+
+    assert_eq!(loaded.delete(&uuids[1..2]).await?, 1);
+    assert_eq!(loaded.len(), 2);
+
+    // Incremental inserts after a load.
+    loaded
+        .add_documents(&Documents::from(vec![Document::create(
+            "5.md",
+            "hello again",
+        )]))
+        .await?;
+    assert_eq!(
+        loaded.similarity_search("hello again", 1).await?[0].path,
+        "5.md"
+    );
+
+    std::fs::remove_file(file)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_hnsw_filter() -> Result<()> {
+    let store = HnswVectorStore::create(HashingEmbedding::create(256)).with_min_similarity(0.0);
+    let documents = (0..20)
+        .map(|x| {
+            Document::create(
+                &format!("{}/{}.md", ["docs", "src"][x % 2], x),
+                "llmchain rust",
+            )
+            .with_metadata("version", x as i64)
+        })
+        .collect::<Vec<_>>();
+    store.add_documents(&Documents::from(documents)).await?;
+
+    let filter = Filter::and(vec![
+        Filter::path_prefix("src/"),
+        Filter::range("version", Some(10i64), None),
+    ]);
+    let similarities = store
+        .similarity_search_with_filter("llmchain rust", 3, &filter)
+        .await?;
+    assert_eq!(similarities.len(), 3);
+    assert!(similarities.iter().all(|(x, _)| filter.matches(x)));
+
+    // Fewer matches than asked for.
+    let filter = Filter::eq("version", 19i64);
+    let similarities = store
+        .similarity_search_with_filter("llmchain rust", 3, &filter)
+        .await?;
+    assert_eq!(similarities.len(), 1);
+    assert_eq!(similarities[0].0.path, "src/19.md");
+
+    let filter = Filter::eq("version", "19");
+    let similarities = store
+        .similarity_search_with_filter("llmchain rust", 3, &filter)
+        .await?;
+    assert!(similarities.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_hnsw_upsert() -> Result<()> {
+    let store = HnswVectorStore::create(HashingEmbedding::create(256)).with_min_similarity(0.0);
+    let first = store.upsert_documents(&documents()).await?;
+    assert_eq!(store.upsert_documents(&documents()).await?, first);
+    assert_eq!(store.len(), 4);
+
+    let uuids = store
+        .upsert_documents(&Documents::from(vec![Document::create(
+            "3.md",
+            "rust examples",
+        )]))
+        .await?;
+    assert_ne!(uuids[0], first[2]);
+    assert_eq!(store.len(), 4);
+    let similarities = store.similarity_search("rust examples", 1).await?;
+    assert_eq!(similarities[0].content, "rust examples");
+
+    // The changed metadata is updated in place.
+    let updated = store
+        .upsert_documents(&Documents::from(vec![Document::create(
+            "3.md",
+            "rust examples",
+        )
+        .with_metadata("line_start", 2usize)]))
+        .await?;
+    assert_eq!(updated, uuids);
+    let similarities = store.similarity_search("rust examples", 1).await?;
+    assert_eq!(similarities[0].metadata["line_start"].as_i64(), Some(2));
+
+    assert_eq!(store.delete_by_path("3.md").await?, 1);
+    assert_eq!(store.len(), 3);
+    assert!(store.document_keys(&["3.md".to_string()]).await?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,270 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use llmchain::Document;
+use llmchain::Documents;
+use llmchain::Embedding;
+use llmchain::Filter;
+use llmchain::HashingEmbedding;
+use llmchain::InMemoryVectorStore;
+use llmchain::SimilarityMetric;
+use llmchain::VectorStore;
+
+fn documents() -> Documents {
+    Documents::from(vec![
+        Document::create("1.md", "hello world").with_metadata("line_start", 1usize),
+        Document::create("2.md", "llmchain rust library for llm"),
+        Document::create("3.md", "llmchain rust examples"),
+        Document::create("4.md", ""),
+    ])
+}
+
+#[tokio::test]
+async fn test_vector_stores_memory() -> Result<()> {
+    let store = InMemoryVectorStore::create(HashingEmbedding::create(256)).with_min_similarity(0.0);
+    store.init().await?;
+
+    let uuids = store.add_documents(&documents()).await?;
+    assert_eq!(uuids.len(), 4);
+    assert_eq!(store.len(), 4);
+
+    let similarities = store.similarity_search("llmchain rust examples", 2).await?;
+    assert_eq!(similarities.len(), 2);
+    assert_eq!(similarities[0].path, "3.md");
+    assert_eq!(similarities[1].path, "2.md");
+
+    // Nothing similar enough.
+    let store = store.with_min_similarity(0.99);
+    let similarities = store.similarity_search("llmchain", 2).await?;
+    assert!(similarities.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_memory_metrics() -> Result<()> {
+    let a = [1.0, 0.0];
+    let b = [0.0, 2.0];
+    assert_eq!(SimilarityMetric::Cosine.similarity(&a, &a), 1.0);
+    assert_eq!(SimilarityMetric::Cosine.similarity(&a, &b), 0.0);
+    assert_eq!(SimilarityMetric::Dot.similarity(&b, &b), 4.0);
+    assert_eq!(SimilarityMetric::L2.similarity(&a, &a), 1.0);
+    assert_eq!(
+        SimilarityMetric::L2.similarity(&[0.0, 0.0], &[3.0, 4.0]),
+        1.0 / 6.0
+    );
+
+    let store = InMemoryVectorStore::create(HashingEmbedding::create(256))
+        .with_metric(SimilarityMetric::L2)
+        .with_min_similarity(0.0);
+    store.add_documents(&documents()).await?;
+    let similarities = store.similarity_search("hello world", 1).await?;
+    assert_eq!(similarities[0].path, "1.md");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_memory_save_load() -> Result<()> {
+    let file = std::env::temp_dir().join(format!("llmchain-store-{}.json", uuid::Uuid::new_v4()));
+    let file = file.to_str().unwrap();
+
+    let embedding = HashingEmbedding::create(256);
+    let store = InMemoryVectorStore::create(embedding.clone()).with_min_similarity(0.0);
+    store.add_documents(&documents()).await?;
+    store.save(file)?;
+
+    let loaded = InMemoryVectorStore::create(embedding).with_min_similarity(0.0);
+    loaded.load(file)?;
+    assert_eq!(loaded.len(), 4);
+    let similarities = loaded.similarity_search("hello world", 1).await?;
+    assert_eq!(similarities[0].metadata["line_start"].as_i64(), Some(1));
+    assert_eq!(
+        loaded.similarity_search("hello", 3).await?,
+        store.similarity_search("hello", 3).await?
+    );
+
+    std::fs::remove_file(file)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_memory_with_score() -> Result<()> {
+    let store = InMemoryVectorStore::create(HashingEmbedding::create(256)).with_min_similarity(0.0);
+    store.add_documents(&documents()).await?;
+
+    let similarities = store
+        .similarity_search_with_score("llmchain rust examples", 3)
+        .await?;
+    assert_eq!(similarities.len(), 2);
+    assert_eq!(similarities[0].0.path, "3.md");
+    assert!((similarities[0].1 - 1.0).abs() < 1e-5);
+    assert!(similarities[1].1 < similarities[0].1);
+    assert!(similarities[1].1 > 0.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_memory_filter() -> Result<()> {
+    let store = InMemoryVectorStore::create(HashingEmbedding::create(256)).with_min_similarity(0.0);
+    let documents = (0..20)
+        .map(|x| {
+            Document::create(
+                &format!("{}/{}.md", ["docs", "src"][x % 2], x),
+                "llmchain rust",
+            )
+            .with_metadata("version", x as i64)
+        })
+        .collect::<Vec<_>>();
+    store.add_documents(&Documents::from(documents)).await?;
+
+    let filter = Filter::and(vec![
+        Filter::path_prefix("src/"),
+        Filter::range("version", Some(10i64), None),
+    ]);
+    let similarities = store
+        .similarity_search_with_filter("llmchain rust", 3, &filter)
+        .await?;
+    assert_eq!(similarities.len(), 3);
+    assert!(similarities.iter().all(|(x, _)| filter.matches(x)));
+
+    // Fewer matches than asked for.
+    let filter = Filter::eq("version", 19i64);
+    let similarities = store
+        .similarity_search_with_filter("llmchain rust", 3, &filter)
+        .await?;
+    assert_eq!(similarities.len(), 1);
+    assert_eq!(similarities[0].0.path, "src/19.md");
+
+    let filter = Filter::eq("version", "19");
+    let similarities = store
+        .similarity_search_with_filter("llmchain rust", 3, &filter)
+        .await?;
+    assert!(similarities.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_memory_delete() -> Result<()> {
+    let store = InMemoryVectorStore::create(HashingEmbedding::create(256)).with_min_similarity(0.0);
+    let uuids = store.add_documents(&documents()).await?;
+    store
+        .add_documents(&Documents::from(vec![Document::create("2.md", "rust")]))
+        .await?;
+
+    assert_eq!(store.delete(&uuids[0..1]).await?, 1);
+    assert_eq!(store.delete(&uuids[0..1]).await?, 0);
+    assert_eq!(store.delete_by_path("2.md").await?, 2);
+    assert_eq!(store.len(), 2);
+
+    let keys = store.document_keys(&["3.md".to_string()]).await?;
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].uuid, uuids[2]);
+    assert_eq!(
+        keys[0].content_md5,
+        documents().iter().nth(2).unwrap().content_md5
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_memory_upsert() -> Result<()> {
+    let store = InMemoryVectorStore::create(HashingEmbedding::create(256)).with_min_similarity(0.0);
+
+    let first = store.upsert_documents(&documents()).await?;
+    assert_eq!(store.len(), 4);
+
+    // Nothing changed, nothing added.
+    let second = store.upsert_documents(&documents()).await?;
+    assert_eq!(first, second);
+    assert_eq!(store.len(), 4);
+
+    // The changed chunk of 2.md replaces the old one, the other paths are left alone.
+    let uuids = store
+        .upsert_documents(&Documents::from(vec![
+            Document::create("2.md", "llmchain rust library"),
+            Document::create("2.md", "llmchain rust library"),
+            Document::create("3.md", "llmchain rust examples"),
+        ]))
+        .await?;
+    assert_eq!(uuids[0], uuids[1]);
+    assert_ne!(uuids[0], first[1]);
+    assert_eq!(uuids[2], first[2]);
+    assert_eq!(store.len(), 4);
+
+    let keys = store.document_keys(&["2.md".to_string()]).await?;
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].uuid, uuids[0]);
+    let similarities = store.similarity_search("llmchain rust library", 1).await?;
+    assert_eq!(similarities[0].content, "llmchain rust library");
+
+    // The duplicates of the append only adds are removed.
+    store.add_documents(&documents()).await?;
+    assert_eq!(store.len(), 8);
+    store.upsert_documents(&documents()).await?;
+    assert_eq!(store.len(), 4);
+
+    // The changed metadata is updated in place, the search returns it.
+    let uuids = store
+        .upsert_documents(&Documents::from(vec![Document::create(
+            "1.md",
+            "hello world",
+        )
+        .with_metadata("line_start", 3usize)]))
+        .await?;
+    let keys = store.document_keys(&["1.md".to_string()]).await?;
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].uuid, uuids[0]);
+    assert_eq!(uuids[0], first[0]);
+    assert_eq!(keys[0].metadata["line_start"].as_i64(), Some(3));
+    let similarities = store.similarity_search("hello world", 1).await?;
+    assert_eq!(similarities[0].metadata["line_start"].as_i64(), Some(3));
+
+    Ok(())
+}
+
+struct ShortEmbedding;
+
+#[async_trait::async_trait]
+impl Embedding for ShortEmbedding {
+    async fn embed_query(&self, input: &str) -> Result<Vec<f32>> {
+        Ok(vec![input.len() as f32])
+    }
+
+    async fn embed_documents(&self, inputs: &Documents) -> Result<Vec<Vec<f32>>> {
+        Ok(inputs
+            .iter()
+            .skip(1)
+            .map(|x| vec![x.content.len() as f32])
+            .collect())
+    }
+}
+
+#[tokio::test]
+async fn test_vector_stores_memory_short_embeddings() -> Result<()> {
+    let store = InMemoryVectorStore::create(Arc::new(ShortEmbedding));
+
+    let error = store.add_documents(&documents()).await.unwrap_err();
+    assert_eq!(error.to_string(), "got 3 embeddings for 4 documents");
+    assert_eq!(store.len(), 0);
+    assert!(store.upsert_documents(&documents()).await.is_err());
+
+    Ok(())
+}
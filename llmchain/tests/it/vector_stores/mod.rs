@@ -13,3 +13,7 @@
 // limitations under the License.
 
 mod databend;
+mod filter;
+mod hnsw;
+mod memory;
+mod sqlite;
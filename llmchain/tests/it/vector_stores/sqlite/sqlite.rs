@@ -0,0 +1,232 @@
+// Copyright 2023 Shafish Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::Result;
+use llmchain::Document;
+use llmchain::Documents;
+use llmchain::Filter;
+use llmchain::HashingEmbedding;
+use llmchain::SqliteVectorStore;
+use llmchain::VectorStore;
+
+fn documents() -> Documents {
+    Documents::from(vec![
+        Document::create("1.md", "hello world"),
+        Document::create("2.md", "llmchain rust library for llm"),
+        Document::create("3.md", "llmchain rust examples"),
+        Document::create("4.md", ""),
+    ])
+}
+
+#[tokio::test]
+async fn test_vector_stores_sqlite() -> Result<()> {
+    let file = std::env::temp_dir().join(format!("llmchain-{}.sqlite", uuid::Uuid::new_v4()));
+    let file = file.to_str().unwrap();
+    let embedding = HashingEmbedding::create(256);
+
+    let store = SqliteVectorStore::create(file, embedding.clone())?.with_min_similarity(0.0);
+    store.init().await?;
+    let uuids = store.add_documents(&documents()).await?;
+    assert_eq!(uuids.len(), 4);
+    drop(store);
+
+    // Persisted in the file.
+    let store = SqliteVectorStore::create(file, embedding)?.with_min_similarity(0.0);
+    store.init().await?;
+    let similarities = store.similarity_search("llmchain rust examples", 2).await?;
+    assert_eq!(similarities.len(), 2);
+    assert_eq!(
+        similarities[0],
+        Document::create("3.md", "llmchain rust examples")
+    );
+    assert_eq!(similarities[1].path, "2.md");
+
+    let store = store.with_min_similarity(0.99);
+    assert!(store.similarity_search("llmchain", 2).await?.is_empty());
+
+    std::fs::remove_file(file)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_sqlite_metadata_filter() -> Result<()> {
+    let store = SqliteVectorStore::create(":memory:", HashingEmbedding::create(256))?
+        .with_table("docs")
+        .with_min_similarity(-1.0);
+    store.init().await?;
+
+    let documents = documents()
+        .iter()
+        .zip(["en", "en", "zh", "en"])
+        .map(|(document, lang)| document.with_metadata("lang", lang))
+        .collect::<Vec<_>>();
+    store.add_documents(&Documents::from(documents)).await?;
+
+    let filter = Filter::eq("lang", "en");
+    let similarities = store
+        .similarity_search_with_filter("llmchain rust examples", 3, &filter)
+        .await?;
+    let paths = similarities
+        .iter()
+        .map(|x| x.0.path.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(paths, vec!["2.md", "1.md"]);
+
+    let filter = Filter::path_prefix("3");
+    let similarities = store
+        .similarity_search_with_filter("llmchain", 3, &filter)
+        .await?;
+    assert_eq!(similarities.len(), 1);
+    assert_eq!(similarities[0].0.path, "3.md");
+    assert_eq!(similarities[0].0.metadata["lang"].as_str(), Some("zh"));
+    assert!(similarities[0].1 > 0.0);
+
+    let filter = Filter::and(vec![
+        Filter::any_of("lang", vec!["en", "de"]),
+        Filter::path_prefix("2"),
+    ]);
+    let similarities = store
+        .similarity_search_with_filter("llmchain rust examples", 3, &filter)
+        .await?;
+    assert_eq!(similarities.len(), 1);
+    assert_eq!(similarities[0].0.path, "2.md");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_sqlite_upsert() -> Result<()> {
+    let store = SqliteVectorStore::create(":memory:", HashingEmbedding::create(256))?
+        .with_min_similarity(0.0);
+    store.init().await?;
+
+    let first = store.upsert_documents(&documents()).await?;
+    assert_eq!(store.upsert_documents(&documents()).await?, first);
+
+    let uuids = store
+        .upsert_documents(&Documents::from(vec![Document::create(
+            "3.md",
+            "rust examples",
+        )]))
+        .await?;
+    assert_ne!(uuids[0], first[2]);
+    let keys = store.document_keys(&["3.md".to_string()]).await?;
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].uuid, uuids[0]);
+
+    // The changed metadata is updated in place.
+    let uuids = store
+        .upsert_documents(&Documents::from(vec![Document::create(
+            "3.md",
+            "rust examples",
+        )
+        .with_metadata("line_start", 2usize)]))
+        .await?;
+    assert_eq!(uuids[0], keys[0].uuid);
+    let keys = store.document_keys(&["3.md".to_string()]).await?;
+    assert_eq!(keys[0].metadata["line_start"].as_i64(), Some(2));
+    assert_eq!(
+        store
+            .update_metadata(&[("unknown".to_string(), Default::default())])
+            .await?,
+        0
+    );
+
+    assert_eq!(store.delete(&first[0..2]).await?, 2);
+    assert_eq!(store.delete(&first[0..2]).await?, 0);
+    assert_eq!(store.delete_by_path("3.md").await?, 1);
+    let keys = store
+        .document_keys(&["1.md".to_string(), "4.md".to_string()])
+        .await?;
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].path, "4.md");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_sqlite_filter_types() -> Result<()> {
+    let store = SqliteVectorStore::create(":memory:", HashingEmbedding::create(256))?
+        .with_min_similarity(-1.0);
+    store.init().await?;
+    store
+        .add_documents(&Documents::from(vec![
+            Document::create("1.md", "hello world")
+                .with_metadata("a.b", "dotted")
+                .with_metadata("it's", 1usize)
+                .with_metadata("draft", true),
+            Document::create("2.md", "hello rust")
+                .with_metadata("a", "nested")
+                .with_metadata("line", 10usize)
+                .with_metadata("draft", "true"),
+        ]))
+        .await?;
+
+    let paths = |filter: Filter| {
+        let store = &store;
+        async move {
+            let similarities = store
+                .similarity_search_with_filter("hello", 5, &filter)
+                .await
+                .unwrap();
+            let mut paths = similarities
+                .into_iter()
+                .map(|x| x.0.path)
+                .collect::<Vec<_>>();
+            paths.sort();
+            paths
+        }
+    };
+
+    // The keys are literal, quotes and dots included.
+    assert_eq!(paths(Filter::eq("a.b", "dotted")).await, vec!["1.md"]);
+    assert_eq!(paths(Filter::eq("it's", 1usize)).await, vec!["1.md"]);
+    assert!(paths(Filter::eq("a", "dotted")).await.is_empty());
+
+    // A boolean never equals its string.
+    assert_eq!(paths(Filter::eq("draft", true)).await, vec!["1.md"]);
+    assert_eq!(paths(Filter::eq("draft", "true")).await, vec!["2.md"]);
+    assert!(paths(Filter::eq("draft", false)).await.is_empty());
+
+    assert_eq!(
+        paths(Filter::range("line", Some(5usize), Some(10usize))).await,
+        vec!["2.md"]
+    );
+    assert!(paths(Filter::range("line", Some("5"), None))
+        .await
+        .is_empty());
+    assert_eq!(
+        paths(Filter::range::<i64>("draft", None, None)).await,
+        vec!["1.md", "2.md"]
+    );
+    assert!(paths(Filter::eq("path", 1usize)).await.is_empty());
+    assert_eq!(
+        paths(Filter::any_of("path", vec!["2.md", "3.md"])).await,
+        vec!["2.md"]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_vector_stores_sqlite_invalid_table() -> Result<()> {
+    let store = SqliteVectorStore::create(":memory:", HashingEmbedding::create(256))?
+        .with_table("docs; DROP TABLE users");
+    let error = store.init().await.unwrap_err();
+    assert!(error.to_string().contains("invalid SQL identifier"));
+    assert!(store.delete_by_path("1.md").await.is_err());
+    assert!(store.similarity_search("hello", 1).await.is_err());
+
+    Ok(())
+}